@@ -1,18 +1,43 @@
 use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::path::Path;
+use swc_core::common::DUMMY_SP;
+use swc_core::plugin::metadata::TransformPluginMetadataContextKind;
 use swc_core::plugin::proxies::TransformPluginProgramMetadata;
 
+use crate::design_tokens::load_design_tokens;
+use crate::error::{emit_error, StoopError};
+use crate::evaluator::validate_utility;
+use crate::hasher::{normalize_name, Case};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct StoopConfig {
     pub theme: Theme,
+    /// Path to a W3C Design Tokens (DTCG) JSON file used as the base theme.
+    /// Tokens set in `theme` take precedence over the file's.
+    pub tokens: Option<String>,
     pub themes: Option<HashMap<String, Theme>>,
-    pub media: Option<HashMap<String, String>>,
+    /// Breakpoints in the order their responsive rules are written, so later ones win
+    pub media: Option<IndexMap<String, String>>,
     pub output: OutputConfig,
     pub prefix: String,
     pub theme_map: HashMap<String, String>,
+    pub utils: HashMap<String, UtilityConfig>,
+    /// Report unknown tokens as errors instead of warnings
+    pub strict: bool,
+    pub theme_selector: ThemeSelectorConfig,
+    pub property_registration: PropertyRegistrationConfig,
+    pub token_output: TokenOutput,
+    pub variable_names: VariableNaming,
+    /// Write variant prop declarations for each file with styled exports to `<output.dir>/types`
+    pub declarations: bool,
+    pub numeric_unit: NumericUnitConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct Theme {
     pub colors: Option<HashMap<String, String>>,
     pub space: Option<HashMap<String, String>>,
@@ -27,12 +52,145 @@ pub struct Theme {
     pub transitions: Option<HashMap<String, String>>,
 }
 
+/// A utility prop declared in `utils`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged, from = "UtilityConfigInput")]
+pub enum UtilityConfig {
+    /// `marginX: { properties: ["marginLeft", "marginRight"], scale: "space" }`
+    /// Shorthand tokens passed to a utility with a scale resolve against that scale
+    Properties {
+        properties: Vec<String>,
+        scale: Option<String>,
+    },
+    /// Source of a pure arrow function, evaluated at build time (see `evaluator`)
+    Function(String),
+}
+
+/// The accepted forms of a `utils` entry, including the `size: ["width", "height"]`
+/// shorthand for a utility without a scale
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UtilityConfigInput {
+    Shorthand(Vec<String>),
+    Properties {
+        properties: Vec<String>,
+        scale: Option<String>,
+    },
+    Function(String),
+}
+
+impl From<UtilityConfigInput> for UtilityConfig {
+    fn from(input: UtilityConfigInput) -> Self {
+        match input {
+            UtilityConfigInput::Shorthand(properties) => UtilityConfig::Properties { properties, scale: None },
+            UtilityConfigInput::Properties { properties, scale } => UtilityConfig::Properties { properties, scale },
+            UtilityConfigInput::Function(source) => UtilityConfig::Function(source),
+        }
+    }
+}
+
+/// How alternate themes are selected in the DOM. Strategies combine: every configured
+/// selector applies the theme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThemeSelectorConfig {
+    /// Attribute holding the theme name, e.g. `data-theme` -> `[data-theme="dark"]`
+    pub attribute: Option<String>,
+    /// Class template, e.g. `theme-{name}` -> `.theme-dark`
+    pub class: Option<String>,
+    /// Apply themes through `prefers-color-scheme` while no theme has been chosen
+    /// explicitly, using `color_schemes`
+    pub media: bool,
+    /// Theme name -> `prefers-color-scheme` value (`dark` or `light`) for `media`.
+    /// When unset, the themes named `dark` and `light` are used.
+    pub color_schemes: Option<HashMap<String, String>>,
+    /// Name of the base theme, so it can be re-applied inside another theme's subtree
+    pub base_name: Option<String>,
+    /// Write every token into each theme block so nested themes (`.theme-dark .theme-light`)
+    /// don't inherit values from the outer theme
+    pub nested: bool,
+}
+
+impl Default for ThemeSelectorConfig {
+    fn default() -> Self {
+        Self {
+            attribute: Some("data-theme".to_string()),
+            class: None,
+            media: false,
+            color_schemes: None,
+            base_name: None,
+            nested: false,
+        }
+    }
+}
+
+/// How tokens are written into component styles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenOutput {
+    /// `var(--stoop-colors-primary)`
+    #[default]
+    Var,
+    /// `var(--stoop-colors-primary, #0070f3)`, for pages where the `:root` block may be missing
+    VarWithFallback,
+    /// `#0070f3`. Alternate themes from `themes` get per-theme override rules
+    Inline,
+}
+
+/// How theme CSS variables are named. Both are namespaced by `prefix`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VariableNaming {
+    /// `--stoop-colors-primary`
+    #[default]
+    Readable,
+    /// `--stoop-1a2b3c`, for smaller production builds. The manifest maps tokens to names
+    Hashed,
+}
+
+/// Registers theme variables with `@property` so they are typed and can be transitioned
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PropertyRegistrationConfig {
+    pub enabled: bool,
+    /// Syntax per scale, overriding the inferred one, e.g. `{ "sizes": "<length>" }`
+    pub syntax: HashMap<String, String>,
+}
+
+/// Unit appended to numbers given to properties that aren't unitless (see `units`)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NumericUnitConfig {
+    pub unit: String,
+    /// Divides numbers before the unit is added, e.g. `16` with `rem` turns `24` into `1.5rem`
+    pub base: Option<f64>,
+}
+
+impl Default for NumericUnitConfig {
+    fn default() -> Self {
+        Self {
+            unit: "px".to_string(),
+            base: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct OutputConfig {
     pub dir: String,
     pub filename: String,
 }
 
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            dir: ".stoop".to_string(),
+            filename: "styles.css".to_string(),
+        }
+    }
+}
+
 impl Default for StoopConfig {
     fn default() -> Self {
         let mut theme_map = HashMap::new();
@@ -42,46 +200,157 @@ impl Default for StoopConfig {
 
         Self {
             theme: Theme::default(),
+            tokens: None,
             themes: None,
             media: None,
-            output: OutputConfig {
-                dir: ".stoop".to_string(),
-                filename: "styles.css".to_string(),
-            },
+            output: OutputConfig::default(),
             prefix: "stoop".to_string(),
             theme_map,
+            utils: HashMap::new(),
+            strict: false,
+            theme_selector: ThemeSelectorConfig::default(),
+            property_registration: PropertyRegistrationConfig::default(),
+            token_output: TokenOutput::default(),
+            variable_names: VariableNaming::default(),
+            declarations: false,
+            numeric_unit: NumericUnitConfig::default(),
         }
     }
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        Self {
-            colors: None,
-            space: None,
-            font_sizes: None,
-            font_weights: None,
-            line_heights: None,
-            letter_spacings: None,
-            sizes: None,
-            radii: None,
-            shadows: None,
-            z_indices: None,
-            transitions: None,
+impl Theme {
+    /// Returns every defined scale as (CSS variable scale name, tokens), in a stable order
+    pub fn scales(&self) -> Vec<(&'static str, &HashMap<String, String>)> {
+        [
+            ("colors", &self.colors),
+            ("space", &self.space),
+            ("font-sizes", &self.font_sizes),
+            ("font-weights", &self.font_weights),
+            ("line-heights", &self.line_heights),
+            ("letter-spacings", &self.letter_spacings),
+            ("sizes", &self.sizes),
+            ("radii", &self.radii),
+            ("shadows", &self.shadows),
+            ("z-indices", &self.z_indices),
+            ("transitions", &self.transitions),
+        ]
+        .into_iter()
+        .filter_map(|(name, scale)| scale.as_ref().map(|tokens| (name, tokens)))
+        .collect()
+    }
+
+    /// Every token as a `scale.token` name using the JS scale name (`fontSizes.lg`), sorted
+    pub fn token_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .scales()
+            .into_iter()
+            .flat_map(|(scale, tokens)| {
+                let scale = normalize_name(scale, Case::Camel);
+                tokens.keys().map(move |key| format!("{}.{}", scale, key))
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Returns this theme with `overrides` applied on top, token by token
+    pub fn merged_with(&self, overrides: &Theme) -> Theme {
+        fn merge(
+            base: &Option<HashMap<String, String>>,
+            overrides: &Option<HashMap<String, String>>,
+        ) -> Option<HashMap<String, String>> {
+            match (base, overrides) {
+                (Some(base), Some(overrides)) => {
+                    let mut merged = base.clone();
+                    merged.extend(overrides.clone());
+                    Some(merged)
+                }
+                (base, None) => base.clone(),
+                (None, overrides) => overrides.clone(),
+            }
+        }
+
+        Theme {
+            colors: merge(&self.colors, &overrides.colors),
+            space: merge(&self.space, &overrides.space),
+            font_sizes: merge(&self.font_sizes, &overrides.font_sizes),
+            font_weights: merge(&self.font_weights, &overrides.font_weights),
+            line_heights: merge(&self.line_heights, &overrides.line_heights),
+            letter_spacings: merge(&self.letter_spacings, &overrides.letter_spacings),
+            sizes: merge(&self.sizes, &overrides.sizes),
+            radii: merge(&self.radii, &overrides.radii),
+            shadows: merge(&self.shadows, &overrides.shadows),
+            z_indices: merge(&self.z_indices, &overrides.z_indices),
+            transitions: merge(&self.transitions, &overrides.transitions),
+        }
+    }
+
+    /// Returns a mutable scale by its JS name (`fontSizes`), or None for unknown scales
+    pub fn scale_mut(&mut self, name: &str) -> Option<&mut Option<HashMap<String, String>>> {
+        match name {
+            "colors" => Some(&mut self.colors),
+            "space" => Some(&mut self.space),
+            "fontSizes" => Some(&mut self.font_sizes),
+            "fontWeights" => Some(&mut self.font_weights),
+            "lineHeights" => Some(&mut self.line_heights),
+            "letterSpacings" => Some(&mut self.letter_spacings),
+            "sizes" => Some(&mut self.sizes),
+            "radii" => Some(&mut self.radii),
+            "shadows" => Some(&mut self.shadows),
+            "zIndices" => Some(&mut self.z_indices),
+            "transitions" => Some(&mut self.transitions),
+            _ => None,
         }
     }
 }
 
 impl StoopConfig {
-    /// Creates config from plugin metadata
+    /// Creates config from the plugin options passed in the bundler config (see `from_json`).
     ///
-    /// Note: Config file loading from styled.config.ts is not yet implemented.
-    /// Configuration must be passed via plugin metadata in next.config.mjs or similar.
-    /// For now, returns default config with minimal theme values.
-    pub fn from_metadata(_metadata: &TransformPluginProgramMetadata) -> Self {
+    /// Without options the default theme is used. Invalid options are reported and fall
+    /// back to an empty default config. Relative paths such as `tokens` resolve from the
+    /// working directory, then the design tokens file is loaded and `utils` are validated,
+    /// reporting any errors.
+    pub fn from_metadata(metadata: &TransformPluginProgramMetadata) -> Self {
+        let source = metadata
+            .get_transform_plugin_config()
+            .filter(|source| !source.trim().is_empty());
+
+        let mut config = match source {
+            Some(source) => Self::from_json(&source).unwrap_or_else(|e| {
+                emit_error(DUMMY_SP, &e);
+                Self::default()
+            }),
+            None => Self::with_default_theme(),
+        };
+
+        if let Some(cwd) = metadata.get_context(&TransformPluginMetadataContextKind::Cwd) {
+            config.resolve_paths(&cwd);
+        }
+
+        if let Err(e) = config.load_tokens() {
+            emit_error(DUMMY_SP, &e);
+        }
+
+        for error in config.validate_utils() {
+            emit_error(DUMMY_SP, &error);
+        }
+
+        config
+    }
+
+    /// Parses the plugin options. Keys are camelCase, as written in the bundler config,
+    /// and every key is optional
+    pub fn from_json(source: &str) -> Result<Self, StoopError> {
+        serde_json::from_str(source)
+            .map_err(|e| StoopError::ConfigError(format!("invalid plugin config: {}", e)))
+    }
+
+    /// Default config with a minimal theme, so the plugin works out of the box when
+    /// no options are passed
+    fn with_default_theme() -> Self {
         let mut config = Self::default();
 
-        // Provide minimal default theme so plugin works out of the box
         let mut colors = HashMap::new();
         colors.insert("primary".to_string(), "#0070f3".to_string());
         colors.insert("secondary".to_string(), "#666".to_string());
@@ -105,6 +374,120 @@ impl StoopConfig {
         config
     }
 
+    /// Makes a relative `tokens` path relative to the project directory rather than
+    /// wherever the compiler happens to run
+    pub fn resolve_paths(&mut self, cwd: &str) {
+        if let Some(tokens) = &self.tokens {
+            if Path::new(tokens).is_relative() {
+                self.tokens = Some(Path::new(cwd).join(tokens).to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    /// Merges the design tokens file from `tokens`, if any, beneath `theme`
+    pub fn load_tokens(&mut self) -> Result<(), StoopError> {
+        let Some(path) = &self.tokens else {
+            return Ok(());
+        };
+
+        let file_theme = load_design_tokens(path)?;
+        self.theme = file_theme.merged_with(&self.theme);
+
+        Ok(())
+    }
+
+    /// Checks every function utility in `utils` up front, so a broken one is reported
+    /// even if no style uses it
+    pub fn validate_utils(&self) -> Vec<StoopError> {
+        let mut names: Vec<&String> = self.utils.keys().collect();
+        names.sort();
+
+        names
+            .into_iter()
+            .filter_map(|name| match &self.utils[name] {
+                UtilityConfig::Function(source) => validate_utility(source)
+                    .err()
+                    .map(|reason| StoopError::UnevaluableUtility(format!("`{}`: {}", name, reason))),
+                UtilityConfig::Properties { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Breakpoint names in the order they appear in `media`, which is the order their
+    /// rules are written in, so a mobile-first list lets wider breakpoints win
+    pub fn breakpoint_names(&self) -> Vec<&str> {
+        self.media
+            .as_ref()
+            .map(|media| media.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Selectors that apply the theme `name`, one per configured strategy
+    pub fn theme_selectors(&self, name: &str) -> Vec<String> {
+        let strategy = &self.theme_selector;
+        let mut selectors = Vec::new();
+
+        if let Some(attribute) = &strategy.attribute {
+            selectors.push(format!("[{}=\"{}\"]", attribute, name));
+        }
+
+        if let Some(class) = &strategy.class {
+            selectors.push(format!(".{}", class.replace("{name}", name)));
+        }
+
+        selectors
+    }
+
+    /// The `prefers-color-scheme` media query that applies theme `name` when the `media`
+    /// strategy is on and `colorSchemes` (or, by default, the name `dark`/`light`) maps
+    /// the theme to a color scheme
+    pub fn theme_media_query(&self, name: &str) -> Option<String> {
+        if !self.theme_selector.media {
+            return None;
+        }
+
+        let scheme = match &self.theme_selector.color_schemes {
+            Some(schemes) => schemes.get(name)?.as_str(),
+            None if name == "dark" || name == "light" => name,
+            None => return None,
+        };
+
+        Some(format!("@media (prefers-color-scheme: {})", scheme))
+    }
+
+    /// Whether `name` is a theme `'@theme name'` style keys can refer to
+    pub fn has_theme(&self, name: &str) -> bool {
+        self.themes.as_ref().is_some_and(|themes| themes.contains_key(name))
+            || self.theme_selector.base_name.as_deref() == Some(name)
+    }
+
+    /// Selector for the root element while no theme has been chosen explicitly, used for
+    /// `prefers-color-scheme` fallbacks
+    pub fn unthemed_root_selector(&self) -> String {
+        let strategy = &self.theme_selector;
+        let mut selector = String::from(":root");
+
+        if let Some(attribute) = &strategy.attribute {
+            selector.push_str(&format!(":not([{}])", attribute));
+        }
+
+        if let Some(class) = &strategy.class {
+            let mut names: Vec<&str> = self
+                .themes
+                .as_ref()
+                .map(|themes| themes.keys().map(String::as_str).collect())
+                .unwrap_or_default();
+            names.extend(strategy.base_name.as_deref());
+            names.sort();
+
+            for name in names {
+                selector.push_str(&format!(":not(.{})", class.replace("{name}", name)));
+            }
+        }
+
+        selector
+    }
+
     /// Attempts to load config from styled.config.ts file
     ///
     /// Note: Not yet implemented. Config file loading would require:
@@ -120,3 +503,100 @@ impl StoopConfig {
         None
     }
 }
+
+/// Whether a global selector is the root element theme selectors are set on, so a theme
+/// applies to it as `html[data-theme="dark"]` rather than to its descendants
+pub fn is_theme_root(selector: &str) -> bool {
+    let selector = selector.trim();
+    selector.eq_ignore_ascii_case("html") || selector == ":root"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let mut config = StoopConfig::from_json(
+            r##"{
+                "theme": { "colors": { "primary": "#0070f3" }, "fontSizes": { "lg": "18px" } },
+                "tokens": "tokens.json",
+                "tokenOutput": "var-with-fallback",
+                "variableNames": "hashed",
+                "numericUnit": { "unit": "rem", "base": 16 },
+                "themeSelector": { "baseName": "light", "colorSchemes": { "dark": "dark" } },
+                "propertyRegistration": { "enabled": true },
+                "declarations": true
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(config.theme.font_sizes.as_ref().unwrap()["lg"], "18px");
+        assert_eq!(config.token_output, TokenOutput::VarWithFallback);
+        assert_eq!(config.variable_names, VariableNaming::Hashed);
+        assert_eq!(config.numeric_unit.unit, "rem");
+        assert_eq!(config.numeric_unit.base, Some(16.0));
+        assert_eq!(config.theme_selector.base_name.as_deref(), Some("light"));
+        assert_eq!(config.theme_selector.attribute.as_deref(), Some("data-theme"));
+        assert!(config.theme_selector.color_schemes.is_some());
+        assert!(config.property_registration.enabled);
+        assert!(config.declarations);
+        assert_eq!(config.output.dir, ".stoop");
+        assert_eq!(config.prefix, "stoop");
+
+        config.resolve_paths("/project");
+        assert_eq!(config.tokens.as_deref(), Some("/project/tokens.json"));
+
+        assert!(StoopConfig::from_json(r#"{ "tokenOutput": "inlined" }"#).is_err());
+    }
+
+    #[test]
+    fn test_utility_config_forms() {
+        let utils: HashMap<String, UtilityConfig> = serde_json::from_str(
+            r#"{
+                "size": ["width", "height"],
+                "marginX": { "properties": ["marginLeft", "marginRight"], "scale": "space" },
+                "linearGradient": "(v) => ({ backgroundImage: v })"
+            }"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &utils["size"],
+            UtilityConfig::Properties { properties, scale: None } if properties == &["width", "height"]
+        ));
+        assert!(matches!(
+            &utils["marginX"],
+            UtilityConfig::Properties { scale: Some(scale), .. } if scale == "space"
+        ));
+        assert!(matches!(&utils["linearGradient"], UtilityConfig::Function(_)));
+    }
+
+    #[test]
+    fn test_validate_utils() {
+        let mut config = StoopConfig::default();
+        config.utils.insert("gradient".to_string(), UtilityConfig::Function("(v) => ({ backgroundImage: v })".to_string()));
+        config.utils.insert("unused".to_string(), UtilityConfig::Function("(v) => ({ width: Math.max(v, 0) })".to_string()));
+        config.utils.insert("size".to_string(), UtilityConfig::Properties { properties: vec!["width".to_string()], scale: None });
+
+        let errors = config.validate_utils();
+        assert_eq!(errors.len(), 1);
+        let message = errors[0].to_string();
+        assert!(message.starts_with("Utility not statically evaluable: `unused`"), "{}", message);
+    }
+
+    #[test]
+    fn test_theme_media_query() {
+        let mut config = StoopConfig::default();
+        assert_eq!(config.theme_media_query("dark"), None);
+
+        config.theme_selector.media = true;
+        assert_eq!(config.theme_media_query("dark").as_deref(), Some("@media (prefers-color-scheme: dark)"));
+        assert_eq!(config.theme_media_query("midnight"), None);
+
+        config.theme_selector.color_schemes =
+            Some(HashMap::from([("midnight".to_string(), "dark".to_string())]));
+        assert_eq!(config.theme_media_query("midnight").as_deref(), Some("@media (prefers-color-scheme: dark)"));
+        assert_eq!(config.theme_media_query("dark"), None);
+    }
+}
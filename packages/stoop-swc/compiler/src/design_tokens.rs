@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::{Map, Value};
+use swc_core::common::DUMMY_SP;
+
+use crate::config::Theme;
+use crate::error::{emit_warning, StoopError};
+use crate::hasher::{normalize_name, Case};
+
+/// Top-level group names that pick a scale regardless of `$type`, as they appear after
+/// lowercasing and dropping `-`/`_`
+const GROUP_SCALES: &[(&[&str], &str)] = &[
+    (&["color", "colors"], "colors"),
+    (&["space", "spacing", "spacings"], "space"),
+    (&["size", "sizes", "sizing"], "sizes"),
+    (&["radius", "radii", "borderradius", "borderradii"], "radii"),
+    (&["fontsize", "fontsizes"], "fontSizes"),
+    (&["fontweight", "fontweights"], "fontWeights"),
+    (&["lineheight", "lineheights"], "lineHeights"),
+    (&["letterspacing", "letterspacings"], "letterSpacings"),
+    (&["shadow", "shadows"], "shadows"),
+    (&["zindex", "zindices"], "zIndices"),
+    (&["transition", "transitions", "duration", "durations"], "transitions"),
+];
+
+/// A token from the file, before its value is converted
+struct RawToken<'a> {
+    pointer: String,
+    scale: &'static str,
+    key: String,
+    token_type: Option<String>,
+    value: &'a Value,
+}
+
+/// Reads a W3C Design Tokens (DTCG) file into a theme
+pub fn load_design_tokens(path: &str) -> Result<Theme, StoopError> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| StoopError::ConfigError(format!("failed to read `tokens` file {}: {}", path, e)))?;
+
+    parse_design_tokens(&source)
+}
+
+/// Maps DTCG tokens onto stoop scales. A top-level group named after a scale (`color`,
+/// `spacing`, `fontSize`, ...) picks the scale, otherwise `$type` does. The remaining
+/// path is camel-cased into the token name, so `color.blue.500` becomes `$colors.blue500`.
+/// Aliases like `{color.blue.500}` become token references.
+///
+/// Tokens whose type has no matching scale (`fontFamily`, `cubicBezier`, ...) are skipped
+/// with a warning. Errors name the JSON pointer of the offending token.
+pub fn parse_design_tokens(source: &str) -> Result<Theme, StoopError> {
+    let root: Value = serde_json::from_str(source)
+        .map_err(|e| StoopError::ConfigError(format!("`tokens` file is not valid JSON: {}", e)))?;
+
+    let Value::Object(root) = &root else {
+        return Err(token_error("", "the file must contain a token group object"));
+    };
+
+    let mut raw_tokens = Vec::new();
+    collect_tokens(root, &mut Vec::new(), None, &mut raw_tokens)?;
+
+    // Camel-casing can give two paths the same name, e.g. `color.blue-500` and
+    // `color.blue.500` both become `$colors.blue500`
+    let mut names: HashMap<(&str, &str), &str> = HashMap::new();
+    for token in &raw_tokens {
+        if let Some(first) = names.insert((token.scale, &token.key), &token.pointer) {
+            return Err(token_error(
+                &token.pointer,
+                &format!(
+                    "becomes `${}.{}`, the same token as `{}`; rename one of them",
+                    token.scale, token.key, first
+                ),
+            ));
+        }
+    }
+
+    // Alias targets by dotted path, e.g. `color.blue.500` -> `$colors.blue500`
+    let references: HashMap<String, String> = raw_tokens
+        .iter()
+        .map(|token| {
+            let path = pointer_to_path(&token.pointer);
+            (path, format!("${}.{}", token.scale, token.key))
+        })
+        .collect();
+
+    let mut theme = Theme::default();
+
+    for token in &raw_tokens {
+        let value = convert_value(token.value, token.token_type.as_deref(), &token.pointer)?;
+        let value = resolve_aliases(&value, &references, &token.pointer)?;
+
+        if let Some(scale) = theme.scale_mut(token.scale) {
+            scale.get_or_insert_with(HashMap::new).insert(token.key.clone(), value);
+        }
+    }
+
+    Ok(theme)
+}
+
+/// Walks groups depth-first, inheriting `$type` from the closest group that sets one
+fn collect_tokens<'a>(
+    group: &'a Map<String, Value>,
+    path: &mut Vec<String>,
+    inherited_type: Option<&str>,
+    tokens: &mut Vec<RawToken<'a>>,
+) -> Result<(), StoopError> {
+    let group_type = match group.get("$type") {
+        Some(Value::String(token_type)) => Some(token_type.as_str()),
+        Some(_) => return Err(token_error(&json_pointer(path), "`$type` must be a string")),
+        None => inherited_type,
+    };
+
+    for (name, child) in group {
+        if name.starts_with('$') {
+            continue;
+        }
+
+        path.push(name.clone());
+        let pointer = json_pointer(path);
+
+        let Value::Object(child) = child else {
+            return Err(token_error(&pointer, "expected a token or group object"));
+        };
+
+        if let Some(value) = child.get("$value") {
+            let token_type = match child.get("$type") {
+                Some(Value::String(token_type)) => Some(token_type.as_str()),
+                Some(_) => return Err(token_error(&pointer, "`$type` must be a string")),
+                None => group_type,
+            };
+
+            match token_scale(path, token_type) {
+                Some((scale, key_path)) if !key_path.is_empty() => tokens.push(RawToken {
+                    pointer,
+                    scale,
+                    key: normalize_name(&key_path.join("-"), Case::Camel),
+                    token_type: token_type.map(str::to_string),
+                    value,
+                }),
+                Some(_) => {
+                    return Err(token_error(&pointer, "a token can't be named after a scale; nest it in a group"));
+                }
+                None => emit_warning(
+                    DUMMY_SP,
+                    &StoopError::ConfigError(format!(
+                        "design token at `{}` was skipped: {} has no matching scale",
+                        pointer,
+                        match token_type {
+                            Some(token_type) => format!("`$type` `{}`", token_type),
+                            None => "a token without `$type`".to_string(),
+                        }
+                    )),
+                ),
+            }
+        } else {
+            collect_tokens(child, path, group_type, tokens)?;
+        }
+
+        path.pop();
+    }
+
+    Ok(())
+}
+
+/// The scale for a token and the part of its path that names it within the scale
+fn token_scale<'p>(path: &'p [String], token_type: Option<&str>) -> Option<(&'static str, &'p [String])> {
+    let group = path[0].to_lowercase().replace(['-', '_'], "");
+
+    if let Some((_, scale)) = GROUP_SCALES.iter().find(|(names, _)| names.contains(&group.as_str())) {
+        return Some((scale, &path[1..]));
+    }
+
+    let scale = match token_type? {
+        "color" => "colors",
+        "dimension" => "space",
+        "fontWeight" => "fontWeights",
+        "shadow" => "shadows",
+        "duration" => "transitions",
+        _ => return None,
+    };
+
+    Some((scale, path))
+}
+
+/// Converts a `$value` to CSS. Alias braces are left for `resolve_aliases`.
+fn convert_value(value: &Value, token_type: Option<&str>, pointer: &str) -> Result<String, StoopError> {
+    match (value, token_type) {
+        (Value::String(s), _) => Ok(s.clone()),
+        (Value::Number(n), _) => Ok(n.to_string()),
+        // `{ "value": 16, "unit": "px" }` from the current draft of the format
+        (Value::Object(dimension), Some("dimension" | "duration")) => {
+            match (dimension.get("value"), dimension.get("unit")) {
+                (Some(Value::Number(n)), Some(Value::String(unit))) => Ok(format!("{}{}", n, unit)),
+                _ => Err(token_error(pointer, "expected `{ \"value\": number, \"unit\": string }`")),
+            }
+        }
+        (Value::Object(shadow), Some("shadow")) => convert_shadow(shadow, pointer),
+        (Value::Array(layers), Some("shadow")) => layers
+            .iter()
+            .map(|layer| match layer {
+                Value::Object(shadow) => convert_shadow(shadow, pointer),
+                Value::String(alias) => Ok(alias.clone()),
+                _ => Err(token_error(pointer, "each shadow layer must be an object")),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|layers| layers.join(", ")),
+        _ => Err(token_error(pointer, "unsupported `$value` for this `$type`")),
+    }
+}
+
+fn convert_shadow(shadow: &Map<String, Value>, pointer: &str) -> Result<String, StoopError> {
+    let mut parts = Vec::new();
+
+    if shadow.get("inset") == Some(&Value::Bool(true)) {
+        parts.push("inset".to_string());
+    }
+
+    for field in ["offsetX", "offsetY", "blur", "spread", "color"] {
+        match shadow.get(field) {
+            Some(value) => parts.push(convert_value(value, Some("dimension"), pointer)?),
+            None if field == "color" || field.starts_with("offset") => {
+                return Err(token_error(pointer, &format!("shadow is missing `{}`", field)));
+            }
+            None => {}
+        }
+    }
+
+    Ok(parts.join(" "))
+}
+
+/// Replaces every `{group.token}` alias with the stoop reference of its target
+fn resolve_aliases(
+    value: &str,
+    references: &HashMap<String, String>,
+    pointer: &str,
+) -> Result<String, StoopError> {
+    let mut result = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err(token_error(pointer, &format!("unclosed alias in `{}`", value)));
+        };
+
+        let alias = &rest[start + 1..start + end];
+        let reference = references.get(alias).ok_or_else(|| {
+            token_error(pointer, &format!("alias `{{{}}}` does not match any token", alias))
+        })?;
+
+        result.push_str(&rest[..start]);
+        result.push_str(reference);
+        rest = &rest[start + end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn token_error(pointer: &str, message: &str) -> StoopError {
+    StoopError::ConfigError(format!("design token at `{}`: {}", pointer, message))
+}
+
+/// RFC 6901 pointer for a token path, e.g. `/color/blue/500`
+fn json_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// The dotted alias path for a pointer, e.g. `/color/blue/500` -> `color.blue.500`
+fn pointer_to_path(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_design_tokens() {
+        let theme = parse_design_tokens(
+            r##"{
+                "color": {
+                    "$type": "color",
+                    "blue": { "500": { "$value": "#3b82f6" } },
+                    "brand-primary": { "$value": "{color.blue.500}" }
+                },
+                "spacing": {
+                    "sm": { "$type": "dimension", "$value": { "value": 8, "unit": "px" } }
+                },
+                "elevation": {
+                    "card": {
+                        "$type": "shadow",
+                        "$value": { "offsetX": "0", "offsetY": "{spacing.sm}", "blur": "16px", "color": "{color.blue.500}" }
+                    }
+                },
+                "font": { "body": { "$type": "fontFamily", "$value": "Inter" } }
+            }"##,
+        )
+        .unwrap();
+
+        let colors = theme.colors.unwrap();
+        assert_eq!(colors["blue500"], "#3b82f6");
+        assert_eq!(colors["brandPrimary"], "$colors.blue500");
+        assert_eq!(theme.space.unwrap()["sm"], "8px");
+        assert_eq!(
+            theme.shadows.unwrap()["elevationCard"],
+            "0 $space.sm 16px $colors.blue500"
+        );
+    }
+
+    #[test]
+    fn test_design_token_errors() {
+        let error = parse_design_tokens(r#"{ "color": { "a/b": { "$type": "color", "$value": "{color.missing}" } } }"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("`/color/a~1b`"), "{}", error);
+        assert!(error.contains("{color.missing}"), "{}", error);
+
+        let error = parse_design_tokens(r#"{ "spacing": { "md": { "$type": "dimension", "$value": true } } }"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("`/spacing/md`"), "{}", error);
+
+        let error = parse_design_tokens(
+            r##"{ "color": { "blue-500": { "$value": "#3b82f6" }, "blue": { "500": { "$value": "#2563eb" } } } }"##,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("`/color/blue-500`"), "{}", error);
+        assert!(error.contains("`/color/blue/500`"), "{}", error);
+        assert!(error.contains("$colors.blue500"), "{}", error);
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::BTreeSet;
 use std::fmt;
+use std::sync::Mutex;
+
+use swc_core::common::{errors::HANDLER, Span};
 
 /// Errors that can occur during CSS compilation
-/// Note: Currently unused but kept for future error handling improvements
+/// Note: Not every variant is reported yet; see `emit_warning` and `emit_error`
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum StoopError {
@@ -11,6 +15,14 @@ pub enum StoopError {
     ConfigError(String),
     /// Invalid style value
     InvalidStyle(String),
+    /// A function utility uses constructs the evaluator does not support
+    UnevaluableUtility(String),
+    /// A token in a shorthand value matches more than one scale
+    AmbiguousToken(String),
+    /// A token that does not exist in the theme
+    UnknownToken(String),
+    /// An alternate theme defines tokens the base theme does not
+    ThemeMismatch(String),
 }
 
 impl fmt::Display for StoopError {
@@ -19,8 +31,50 @@ impl fmt::Display for StoopError {
             StoopError::WriteError(msg) => write!(f, "Failed to write CSS: {}", msg),
             StoopError::ConfigError(msg) => write!(f, "Config error: {}", msg),
             StoopError::InvalidStyle(msg) => write!(f, "Invalid style: {}", msg),
+            StoopError::UnevaluableUtility(msg) => write!(f, "Utility not statically evaluable: {}", msg),
+            StoopError::AmbiguousToken(msg) => write!(f, "Ambiguous token: {}", msg),
+            StoopError::UnknownToken(msg) => write!(f, "Unknown token: {}", msg),
+            StoopError::ThemeMismatch(msg) => write!(f, "Theme mismatch: {}", msg),
         }
     }
 }
 
 impl std::error::Error for StoopError {}
+
+/// Reports a warning through the SWC handler, falling back to stderr outside the plugin
+pub fn emit_warning(span: Span, error: &StoopError) {
+    let message = format!("[stoop-swc] {}", error);
+
+    if HANDLER.is_set() {
+        HANDLER.with(|handler| handler.struct_span_warn(span, &message).emit());
+    } else {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+/// Messages already reported by `emit_warning_once`
+static REPORTED_WARNINGS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Reports a warning the first time it occurs, for config-level problems that would
+/// otherwise repeat for every compiled file
+pub fn emit_warning_once(span: Span, error: &StoopError) {
+    let first = REPORTED_WARNINGS
+        .lock()
+        .map(|mut reported| reported.insert(error.to_string()))
+        .unwrap_or(true);
+
+    if first {
+        emit_warning(span, error);
+    }
+}
+
+/// Reports an error through the SWC handler, falling back to stderr outside the plugin
+pub fn emit_error(span: Span, error: &StoopError) {
+    let message = format!("[stoop-swc] {}", error);
+
+    if HANDLER.is_set() {
+        HANDLER.with(|handler| handler.struct_span_err(span, &message).emit());
+    } else {
+        eprintln!("Error: {}", message);
+    }
+}
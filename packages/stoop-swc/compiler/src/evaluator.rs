@@ -0,0 +1,352 @@
+use swc_core::common::{sync::Lrc, FileName, SourceMap};
+use swc_core::ecma::ast::*;
+use swc_core::ecma::parser::{parse_file_as_expr, Syntax, TsSyntax};
+
+use crate::types::{StylePart, StyleValue};
+
+/// A JS value produced while evaluating a utility function
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Str(s) => !s.is_empty(),
+            Value::Num(n) => *n != 0.0 && !n.is_nan(),
+            Value::Bool(b) => *b,
+            Value::Object(_) => true,
+        }
+    }
+
+    fn to_css(&self) -> Result<String, String> {
+        match self {
+            Value::Str(s) => Ok(s.clone()),
+            Value::Num(n) => Ok(n.to_string()),
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::Object(_) => Err("nested objects in the returned styles are not supported".to_string()),
+        }
+    }
+}
+
+/// Evaluates a utility given as arrow function source, e.g.
+/// ``(v) => ({ backgroundImage: `linear-gradient(${v})` })``, and returns its declarations
+///
+/// Only pure functions are supported: a single parameter, an expression body returning an
+/// object, and string/number literals, template literals, `+`, comparisons and ternaries.
+/// The error describes the first unsupported construct.
+pub fn evaluate_utility(source: &str, argument: &StyleValue) -> Result<Vec<(String, String)>, String> {
+    let (param, body) = parse_utility(source)?;
+
+    let evaluator = Evaluator {
+        param,
+        argument: argument_value(argument),
+    };
+
+    match evaluator.eval(&body)? {
+        Value::Object(props) => props
+            .into_iter()
+            .map(|(property, value)| value.to_css().map(|css| (property, css)))
+            .collect(),
+        _ => Err("function must return a style object".to_string()),
+    }
+}
+
+/// Checks that a utility only uses supported constructs, without an argument, so a
+/// broken utility is reported when the config loads rather than when it is first used.
+/// Every branch is checked, including ones a given argument would never reach.
+pub fn validate_utility(source: &str) -> Result<(), String> {
+    let (param, body) = parse_utility(source)?;
+
+    let evaluator = Evaluator {
+        param,
+        argument: Value::Str(String::new()),
+    };
+
+    evaluator.check_returns_object(&body)?;
+    evaluator.check(&body)
+}
+
+/// The parameter name and expression body of a utility
+fn parse_utility(source: &str) -> Result<(String, Box<Expr>), String> {
+    let arrow = parse_arrow(source)?;
+
+    if arrow.is_async || arrow.is_generator {
+        return Err("async and generator functions are not supported".to_string());
+    }
+
+    let param = match arrow.params.as_slice() {
+        [Pat::Ident(ident)] => ident.id.sym.to_string(),
+        _ => return Err("expected exactly one plain parameter".to_string()),
+    };
+
+    match *arrow.body {
+        ArrowFunctionBody::Expr(expr) => Ok((param, expr)),
+        _ => Err("function body must be a single expression, e.g. `(v) => ({ ... })`".to_string()),
+    }
+}
+
+fn parse_arrow(source: &str) -> Result<ArrowExpr, String> {
+    let cm: Lrc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(
+        Lrc::new(FileName::Custom("utility.ts".into())),
+        source.to_string(),
+    );
+
+    let expr = parse_file_as_expr(
+        &fm,
+        Syntax::Typescript(TsSyntax::default()),
+        Default::default(),
+        None,
+        &mut vec![],
+    )
+    .map_err(|_| "source is not a valid expression".to_string())?;
+
+    match *expr {
+        Expr::Arrow(arrow) => Ok(arrow),
+        Expr::Paren(ParenExpr { expr, .. }) => match *expr {
+            Expr::Arrow(arrow) => Ok(arrow),
+            _ => Err("expected an arrow function".to_string()),
+        },
+        _ => Err("expected an arrow function".to_string()),
+    }
+}
+
+/// The style value passed to the utility: numbers stay numbers, tokens are passed as
+/// written (`$md`) and resolved after evaluation
+fn argument_value(value: &StyleValue) -> Value {
+    let raw = match value {
+        StyleValue::Static(s) | StyleValue::Token(s) => s.clone(),
+        StyleValue::Compound(parts) => parts
+            .iter()
+            .map(|part| match part {
+                StylePart::Static(s) | StylePart::Token(s) => s.as_str(),
+            })
+            .collect(),
+    };
+
+    match raw.parse::<f64>() {
+        Ok(n) => Value::Num(n),
+        Err(_) => Value::Str(raw),
+    }
+}
+
+/// The keys and value expressions of an object literal, which may only hold
+/// `key: value` properties with static keys
+fn object_entries(obj: &ObjectLit) -> Result<Vec<(String, &Expr)>, String> {
+    obj.props
+        .iter()
+        .map(|prop| {
+            let kv = match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::KeyValue(kv) => kv,
+                    _ => return Err("only `key: value` properties are supported".to_string()),
+                },
+                PropOrSpread::Spread(_) => return Err("object spread is not supported".to_string()),
+            };
+
+            let key = match &kv.key {
+                PropName::Ident(ident) => ident.sym.to_string(),
+                PropName::Str(s) => String::from_utf8_lossy(s.value.as_bytes()).into_owned(),
+                _ => return Err("computed property keys are not supported".to_string()),
+            };
+
+            Ok((key, &*kv.value))
+        })
+        .collect()
+}
+
+struct Evaluator {
+    param: String,
+    argument: Value,
+}
+
+impl Evaluator {
+    fn eval(&self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Paren(paren) => self.eval(&paren.expr),
+            Expr::Ident(ident) if *ident.sym == *self.param => Ok(self.argument.clone()),
+            Expr::Ident(ident) => Err(format!("reference to `{}` outside the function", ident.sym)),
+            Expr::Lit(Lit::Str(s)) => Ok(Value::Str(String::from_utf8_lossy(s.value.as_bytes()).into_owned())),
+            Expr::Lit(Lit::Num(n)) => Ok(Value::Num(n.value)),
+            Expr::Lit(Lit::Bool(b)) => Ok(Value::Bool(b.value)),
+            Expr::Tpl(tpl) => self.eval_template(tpl),
+            Expr::Object(obj) => self.eval_object(obj),
+            Expr::Cond(cond) => {
+                if self.eval(&cond.test)?.is_truthy() {
+                    self.eval(&cond.cons)
+                } else {
+                    self.eval(&cond.alt)
+                }
+            }
+            Expr::Unary(unary) if unary.op == UnaryOp::Bang => {
+                Ok(Value::Bool(!self.eval(&unary.arg)?.is_truthy()))
+            }
+            Expr::Unary(unary) if unary.op == UnaryOp::Minus => match self.eval(&unary.arg)? {
+                Value::Num(n) => Ok(Value::Num(-n)),
+                _ => Err("unary `-` is only supported on numbers".to_string()),
+            },
+            Expr::Bin(bin) => self.eval_binary(bin),
+            Expr::Call(_) => Err("function calls are not supported".to_string()),
+            Expr::Member(_) => Err("property access is not supported".to_string()),
+            _ => Err("unsupported expression".to_string()),
+        }
+    }
+
+    /// Mirrors `eval` without evaluating, visiting both sides of every branch
+    fn check(&self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Paren(paren) => self.check(&paren.expr),
+            Expr::Ident(ident) if *ident.sym == *self.param => Ok(()),
+            Expr::Ident(ident) => Err(format!("reference to `{}` outside the function", ident.sym)),
+            Expr::Lit(Lit::Str(_) | Lit::Num(_) | Lit::Bool(_)) => Ok(()),
+            Expr::Tpl(tpl) => tpl.exprs.iter().try_for_each(|expr| self.check(expr)),
+            Expr::Object(obj) => object_entries(obj)?
+                .into_iter()
+                .try_for_each(|(_, value)| self.check(value)),
+            Expr::Cond(cond) => {
+                self.check(&cond.test)?;
+                self.check(&cond.cons)?;
+                self.check(&cond.alt)
+            }
+            Expr::Unary(unary) if matches!(unary.op, UnaryOp::Bang | UnaryOp::Minus) => self.check(&unary.arg),
+            Expr::Bin(bin) => match bin.op {
+                BinaryOp::EqEqEq
+                | BinaryOp::EqEq
+                | BinaryOp::NotEqEq
+                | BinaryOp::NotEq
+                | BinaryOp::Add
+                | BinaryOp::Sub
+                | BinaryOp::Mul
+                | BinaryOp::Div
+                | BinaryOp::LogicalAnd
+                | BinaryOp::LogicalOr => {
+                    self.check(&bin.left)?;
+                    self.check(&bin.right)
+                }
+                op => Err(format!("operator `{}` is not supported", op)),
+            },
+            Expr::Call(_) => Err("function calls are not supported".to_string()),
+            Expr::Member(_) => Err("property access is not supported".to_string()),
+            _ => Err("unsupported expression".to_string()),
+        }
+    }
+
+    /// Every branch of the body must end in an object literal
+    fn check_returns_object(&self, expr: &Expr) -> Result<(), String> {
+        match expr {
+            Expr::Paren(paren) => self.check_returns_object(&paren.expr),
+            Expr::Object(_) => Ok(()),
+            Expr::Cond(cond) => {
+                self.check_returns_object(&cond.cons)?;
+                self.check_returns_object(&cond.alt)
+            }
+            _ => Err("function must return a style object".to_string()),
+        }
+    }
+
+    fn eval_template(&self, tpl: &Tpl) -> Result<Value, String> {
+        let mut result = String::new();
+
+        for (index, quasi) in tpl.quasis.iter().enumerate() {
+            match &quasi.cooked {
+                Some(cooked) => result.push_str(&String::from_utf8_lossy(cooked.as_bytes())),
+                None => result.push_str(&quasi.raw),
+            }
+
+            if let Some(expr) = tpl.exprs.get(index) {
+                result.push_str(&self.eval(expr)?.to_css()?);
+            }
+        }
+
+        Ok(Value::Str(result))
+    }
+
+    fn eval_object(&self, obj: &ObjectLit) -> Result<Value, String> {
+        object_entries(obj)?
+            .into_iter()
+            .map(|(key, value)| Ok((key, self.eval(value)?)))
+            .collect::<Result<_, String>>()
+            .map(Value::Object)
+    }
+
+    fn eval_binary(&self, bin: &BinExpr) -> Result<Value, String> {
+        let left = self.eval(&bin.left)?;
+
+        // Short-circuit like JS so the untaken side is never evaluated
+        match bin.op {
+            BinaryOp::LogicalAnd if !left.is_truthy() => return Ok(left),
+            BinaryOp::LogicalOr if left.is_truthy() => return Ok(left),
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr => return self.eval(&bin.right),
+            _ => {}
+        }
+
+        let right = self.eval(&bin.right)?;
+
+        match (bin.op, left, right) {
+            (BinaryOp::EqEqEq | BinaryOp::EqEq, left, right) => Ok(Value::Bool(left == right)),
+            (BinaryOp::NotEqEq | BinaryOp::NotEq, left, right) => Ok(Value::Bool(left != right)),
+            (BinaryOp::Add, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a + b)),
+            (BinaryOp::Add, left, right) => Ok(Value::Str(format!("{}{}", left.to_css()?, right.to_css()?))),
+            (BinaryOp::Sub, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a - b)),
+            (BinaryOp::Mul, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a * b)),
+            (BinaryOp::Div, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a / b)),
+            (op, _, _) => Err(format!("operator `{}` is not supported on these operands", op)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StoopError;
+
+    fn evaluate(source: &str, argument: &str) -> Result<Vec<(String, String)>, String> {
+        evaluate_utility(source, &StyleValue::Static(argument.to_string()))
+    }
+
+    fn unevaluable(source: &str) -> String {
+        let reason = validate_utility(source).expect_err(source);
+        StoopError::UnevaluableUtility(reason).to_string()
+    }
+
+    #[test]
+    fn test_evaluate_utility() {
+        assert_eq!(
+            evaluate("(v) => ({ backgroundImage: `linear-gradient(${v})` })", "red, blue").unwrap(),
+            [("backgroundImage".to_string(), "linear-gradient(red, blue)".to_string())]
+        );
+        assert_eq!(
+            evaluate("(v: string) => ({ width: v, height: v === 'auto' ? 'auto' : v })", "10px").unwrap(),
+            [("width".to_string(), "10px".to_string()), ("height".to_string(), "10px".to_string())]
+        );
+        assert_eq!(
+            evaluate("v => v === 4 ? { marginTop: v * 2 } : { marginTop: 0 }", "4").unwrap(),
+            [("marginTop".to_string(), "8".to_string())]
+        );
+        assert!(validate_utility("(v) => ({ gap: v || '$md' })").is_ok());
+    }
+
+    #[test]
+    fn test_unevaluable_utilities() {
+        for source in [
+            "(a, b) => ({ width: a })",
+            "({ size }) => ({ width: size })",
+            "(v) => ({ width: Math.max(v, 0) })",
+            "(v) => ({ color: theme })",
+            "(v) => v === 'x' ? { color: v } : { color: fallback }",
+            "(v) => { return { width: v } }",
+            "(v) => v",
+            "(v) => v > 0 ? { marginTop: v } : { marginTop: 0 }",
+        ] {
+            assert!(unevaluable(source).starts_with("Utility not statically evaluable: "), "{}", source);
+        }
+
+        assert!(unevaluable("(v) => ({ color: theme })").contains("`theme`"));
+        assert!(unevaluable("(v) => ({ width: Math.max(v, 0) })").contains("function calls"));
+    }
+}
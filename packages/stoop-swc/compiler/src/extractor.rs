@@ -1,17 +1,25 @@
+use swc_core::common::{Span, Spanned};
 use swc_core::ecma::ast::*;
 use std::collections::HashMap;
-use crate::config::StoopConfig;
-use crate::types::{StyleExtraction, StyleValue, StylePart};
-use crate::utils::{is_utility_prop, convert_special_utility};
+use crate::config::{is_theme_root, StoopConfig, Theme, UtilityConfig};
+use crate::error::{emit_error, emit_warning, StoopError};
+use crate::evaluator::evaluate_utility;
+use crate::hasher::{normalize_name, Case};
+use crate::tokens::{parse_style_string, shorthand_slots, Slot, TokenResolver, VariableNamer};
+use crate::types::{CompoundVariant, GlobalRule, StyleBlock, StyleExtraction, StyleValue, StylePart};
+use crate::units::numeric_value;
+use crate::utils::{expand_utility, find_utility};
 
 pub struct StyleExtractor {
     config: StoopConfig,
+    token_resolver: TokenResolver,
 }
 
 impl StyleExtractor {
     pub fn new(config: &StoopConfig) -> Self {
         Self {
             config: config.clone(),
+            token_resolver: TokenResolver::new(&config.theme, &VariableNamer::new(config)),
         }
     }
 
@@ -21,17 +29,49 @@ impl StyleExtractor {
         component_name: &str,
     ) -> StyleExtraction {
         let (element, composed_class) = self.extract_element(call);
+        let base = self.extract_base_block(call.args.get(1));
+        let variants = self.extract_variants(call.args.get(2));
+        let compound_variants = self.extract_compound_variants(call.args.get(2), &variants);
+        let default_variants = self.extract_default_variants(call.args.get(2), &variants);
 
         StyleExtraction {
             component_name: component_name.to_string(),
             element,
-            base_styles: self.extract_base_styles(call),
-            variants: self.extract_variants(call),
-            nested_selectors: self.extract_nested_selectors(call),
+            base_styles: base.styles,
+            variants,
+            nested_selectors: base.nested_selectors,
+            compound_variants,
+            default_variants,
             composed_component_class: composed_class,
         }
     }
 
+    /// Extracts a standalone `css(styles, variants?)` call, which has no element argument
+    pub fn extract_from_css_call(&self, call: &CallExpr, name: &str) -> StyleExtraction {
+        let base = self.extract_base_block(call.args.first());
+        let variants = self.extract_variants(call.args.get(1));
+        let compound_variants = self.extract_compound_variants(call.args.get(1), &variants);
+        let default_variants = self.extract_default_variants(call.args.get(1), &variants);
+
+        StyleExtraction {
+            component_name: name.to_string(),
+            element: String::new(),
+            base_styles: base.styles,
+            variants,
+            nested_selectors: base.nested_selectors,
+            compound_variants,
+            default_variants,
+            composed_component_class: None,
+        }
+    }
+
+    fn extract_base_block(&self, arg: Option<&ExprOrSpread>) -> StyleBlock {
+        match arg.map(|arg| &*arg.expr) {
+            Some(Expr::Object(obj)) => self.extract_style_block(obj),
+            _ => StyleBlock::default(),
+        }
+    }
+
     fn extract_element(&self, call: &CallExpr) -> (String, Option<String>) {
         if let Some(arg) = call.args.first() {
             match &*arg.expr {
@@ -68,30 +108,138 @@ impl StyleExtractor {
         ("div".to_string(), None)
     }
 
-    fn extract_base_styles(&self, call: &CallExpr) -> HashMap<String, StyleValue> {
-        let mut styles = HashMap::new();
-        let mut utility_props = HashMap::new();
+    /// Extracts declarations and one level of nested selectors/at-rules from a style object
+    pub fn extract_style_block(&self, obj: &ObjectLit) -> StyleBlock {
+        let mut block = StyleBlock::default();
 
-        if call.args.len() < 2 {
-            return styles;
-        }
+        for prop in &obj.props {
+            if let PropOrSpread::Prop(prop) = prop {
+                if let Prop::KeyValue(kv) = &**prop {
+                    let key = self.extract_prop_key(&kv.key);
 
-        if let Expr::Object(obj) = &*call.args[1].expr {
-            for prop in &obj.props {
-                if let PropOrSpread::Prop(prop) = prop {
-                    if let Prop::KeyValue(kv) = &**prop {
-                        let key = self.extract_prop_key(&kv.key);
+                    if !is_nested_key(&key) {
+                        continue;
+                    }
 
-                        if key.starts_with('&') || key.starts_with(':') || key.starts_with('@') {
+                    if let Some(theme_name) = key.strip_prefix("@theme ") {
+                        if !self.config.has_theme(theme_name.trim()) {
+                            emit_error(
+                                kv.key.span(),
+                                &StoopError::InvalidStyle(format!(
+                                    "`{}` refers to unknown theme `{}`. Themes come from `themes` or `themeSelector.baseName` in the config.",
+                                    key,
+                                    theme_name.trim(),
+                                )),
+                            );
                             continue;
                         }
+                    }
 
-                        let value = self.extract_style_value(&kv.value);
+                    if let Expr::Object(nested_obj) = &*kv.value {
+                        self.report_deep_nesting(&key, nested_obj);
+                        block.nested_selectors.insert(key, self.extract_declarations(nested_obj));
+                    }
+                }
+            }
+        }
 
-                        // Check if this is a utility prop (mb, mt, px, py, etc.)
-                        if is_utility_prop(&key) {
-                            utility_props.insert(key, value);
-                        } else {
+        block.styles = self.extract_declarations(obj);
+        block
+    }
+
+    /// Reports selectors and at-rules nested inside another one (`'&:hover': { '@bp2': {...} }`).
+    /// Only one level of nesting is compiled, so they would otherwise be dropped silently.
+    fn report_deep_nesting(&self, parent: &str, obj: &ObjectLit) {
+        for prop in &obj.props {
+            if let PropOrSpread::Prop(prop) = prop {
+                if let Prop::KeyValue(kv) = &**prop {
+                    let key = self.extract_prop_key(&kv.key);
+
+                    if !is_nested_key(&key) {
+                        continue;
+                    }
+
+                    let error = StoopError::InvalidStyle(format!(
+                        "`{}` inside `{}` is ignored: nested selectors and at-rules can't contain further nested rules",
+                        key, parent,
+                    ));
+
+                    if self.config.strict {
+                        emit_error(kv.key.span(), &error);
+                    } else {
+                        emit_warning(kv.key.span(), &error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Extracts plain declarations from a style object, expanding utility props
+    fn extract_declarations(&self, obj: &ObjectLit) -> HashMap<String, StyleValue> {
+        let mut styles = HashMap::new();
+        let mut utility_props = Vec::new();
+
+        for prop in &obj.props {
+            if let PropOrSpread::Prop(prop) = prop {
+                if let Prop::KeyValue(kv) = &**prop {
+                    let key = self.extract_prop_key(&kv.key);
+
+                    if is_nested_key(&key) {
+                        continue;
+                    }
+
+                    // `'background-color'` resolves tokens and utilities like `backgroundColor`
+                    let key = normalize_name(&key, Case::Camel);
+                    let value = self.extract_style_value(&kv.value);
+
+                    // Numbers get a unit by the property they end up on, like React's inline styles
+                    let number = numeric_literal(&kv.value);
+
+                    // Utilities from `config.utils` or the built-ins (mb, px, w, etc.)
+                    match find_utility(&key, &self.config) {
+                        Some(UtilityConfig::Properties { properties, scale }) => {
+                            let mut expanded = expand_utility(&properties, scale.as_deref(), &value);
+
+                            if let Some(number) = number {
+                                for (property, value) in expanded.iter_mut() {
+                                    *value = StyleValue::Static(numeric_value(property, number, &self.config.numeric_unit));
+                                }
+                            }
+
+                            // Every property gets the same value, so report it once under the utility's name
+                            if let Some(expanded_value) = expanded.values().next() {
+                                self.check_tokens(&key, expanded_value, kv.span());
+                            }
+
+                            utility_props.push(expanded);
+                        }
+                        Some(UtilityConfig::Function(source)) => match evaluate_utility(&source, &value) {
+                            Ok(declarations) => utility_props.push(
+                                declarations
+                                    .into_iter()
+                                    .map(|(property, css)| {
+                                        // A bare number can only come from arithmetic or the argument
+                                        let css = match css.parse::<f64>() {
+                                            Ok(n) => numeric_value(&property, n, &self.config.numeric_unit),
+                                            Err(_) => css,
+                                        };
+                                        let value = parse_style_string(css);
+                                        self.check_tokens(&property, &value, kv.span());
+                                        (property, value)
+                                    })
+                                    .collect(),
+                            ),
+                            Err(reason) => emit_error(
+                                kv.key.span(),
+                                &StoopError::UnevaluableUtility(format!("`{}`: {}", key, reason)),
+                            ),
+                        },
+                        None => {
+                            let value = match number {
+                                Some(number) => StyleValue::Static(numeric_value(&key, number, &self.config.numeric_unit)),
+                                None => value,
+                            };
+                            self.check_tokens(&key, &value, kv.span());
                             styles.insert(key, value);
                         }
                     }
@@ -99,71 +247,266 @@ impl StyleExtractor {
             }
         }
 
-        // Convert utility props to CSS properties and merge into styles
-        for (utility, value) in utility_props {
-            let converted = convert_special_utility(&utility, value);
-            styles.extend(converted);
+        // Expanded utilities win over plain declarations for the same property
+        for expanded in utility_props {
+            styles.extend(expanded);
         }
 
         styles
     }
 
-    fn extract_nested_selectors(&self, call: &CallExpr) -> HashMap<String, HashMap<String, StyleValue>> {
-        let mut nested = HashMap::new();
+    /// Reports tokens missing from the theme (an error in strict mode), and tokens in
+    /// shorthand values (`1px solid $md`) that match more than one scale
+    fn check_tokens(&self, property: &str, value: &StyleValue, span: Span) {
+        // Tokens with the shorthand slot they sit in, if any
+        let tokens: Vec<(&String, Option<Slot>)> = match value {
+            StyleValue::Static(_) => Vec::new(),
+            StyleValue::Token(token) => vec![(token, None)],
+            StyleValue::Compound(parts) => {
+                let slots = shorthand_slots(parts, property);
+
+                parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        StylePart::Token(token) => Some(token),
+                        StylePart::Static(_) => None,
+                    })
+                    .enumerate()
+                    .map(|(index, token)| (token, slots.as_ref().and_then(|slots| slots.get(index).copied())))
+                    .collect()
+            }
+        };
+
+        for (token, slot) in tokens {
+            if let Err(suggestion) = self.token_resolver.check_token(token) {
+                let message = match suggestion {
+                    Some(suggestion) => format!("`{}` in `{}` is not defined in the theme. Did you mean `{}`?", token, property, suggestion),
+                    None => format!("`{}` in `{}` is not defined in the theme", token, property),
+                };
+                let error = StoopError::UnknownToken(message);
+
+                if self.config.strict {
+                    emit_error(span, &error);
+                } else {
+                    emit_warning(span, &error);
+                }
+                continue;
+            }
+
+            if let Some(scale) = self.token_resolver.unnegatable_scale(token, property, slot) {
+                let error = StoopError::InvalidStyle(format!(
+                    "`{}` in `{}` negates a {} token, which has no negative value, so it is written as-is. Only space and sizes tokens can be negated.",
+                    token, property, scale,
+                ));
+
+                if self.config.strict {
+                    emit_error(span, &error);
+                } else {
+                    emit_warning(span, &error);
+                }
+            }
 
-        if call.args.len() < 2 {
-            return nested;
+            // Only tokens in a shorthand slot can match more than one scale
+            let Some(slot) = slot else {
+                continue;
+            };
+
+            if let Some(scales) = self.token_resolver.ambiguous_scales(token, slot) {
+                let name = token.trim_start_matches('-').trim_start_matches('$');
+                emit_warning(
+                    span,
+                    &StoopError::AmbiguousToken(format!(
+                        "`{}` in `{}` matches the {} scales; resolving against {}. Use `${}.{}` to pick one explicitly.",
+                        token,
+                        property,
+                        scales.join(", "),
+                        scales[0],
+                        scales[1],
+                        name,
+                    )),
+                );
+            }
         }
+    }
 
-        if let Expr::Object(obj) = &*call.args[1].expr {
-            for prop in &obj.props {
-                if let PropOrSpread::Prop(prop) = prop {
-                    if let Prop::KeyValue(kv) = &**prop {
-                        let key = self.extract_prop_key(&kv.key);
+    /// Checks whether a style object can be compiled at build time: plain keys, literal
+    /// values and nested objects only
+    pub fn is_static_style_object(&self, obj: &ObjectLit) -> bool {
+        obj.props.iter().all(|prop| match prop {
+            PropOrSpread::Prop(prop) => match &**prop {
+                Prop::KeyValue(kv) => {
+                    matches!(kv.key, PropName::Ident(_) | PropName::Str(_))
+                        && self.is_static_style_value(&kv.value)
+                }
+                _ => false,
+            },
+            PropOrSpread::Spread(_) => false,
+        })
+    }
 
-                        if !key.starts_with('&') && !key.starts_with(':') && !key.starts_with('@') {
-                            continue;
+    fn is_static_style_value(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Lit(Lit::Str(_)) | Expr::Lit(Lit::Num(_)) | Expr::Lit(Lit::Bool(_)) => true,
+            Expr::Unary(_) => numeric_literal(expr).is_some(),
+            Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+            Expr::Object(obj) => self.is_static_style_object(obj),
+            Expr::Paren(paren) => self.is_static_style_value(&paren.expr),
+            _ => false,
+        }
+    }
+
+    /// Extracts the rules of a `globalCss({ ... })` call in source order
+    pub fn extract_global(&self, call: &CallExpr) -> Vec<GlobalRule> {
+        match call.args.first().map(|arg| &*arg.expr) {
+            Some(Expr::Object(obj)) => self.extract_global_rules(obj),
+            _ => Vec::new(),
+        }
+    }
+
+    fn extract_global_rules(&self, obj: &ObjectLit) -> Vec<GlobalRule> {
+        let mut rules = Vec::new();
+
+        for prop in &obj.props {
+            if let PropOrSpread::Prop(prop) = prop {
+                if let Prop::KeyValue(kv) = &**prop {
+                    let key = self.extract_prop_key(&kv.key);
+
+                    if is_declaration_at_rule(&key) {
+                        // '@font-face' accepts a single block or an array of blocks
+                        let blocks = match &*kv.value {
+                            Expr::Object(block_obj) => vec![self.extract_declarations(block_obj)],
+                            Expr::Array(array) => array
+                                .elems
+                                .iter()
+                                .flatten()
+                                .filter_map(|elem| match &*elem.expr {
+                                    Expr::Object(block_obj) => Some(self.extract_declarations(block_obj)),
+                                    _ => None,
+                                })
+                                .collect(),
+                            _ => continue,
+                        };
+
+                        rules.push(GlobalRule::AtRule { name: key, blocks });
+                    } else if let Expr::Object(value_obj) = &*kv.value {
+                        if key.starts_with('@') {
+                            rules.push(GlobalRule::Group {
+                                query: key,
+                                rules: self.extract_global_rules(value_obj),
+                            });
+                        } else {
+                            let mut block = self.extract_style_block(value_obj);
+
+                            if !is_theme_root(&key) {
+                                self.reject_global_themes(&key, value_obj, &mut block);
+                            }
+
+                            rules.push(GlobalRule::Selector { selector: key, block });
                         }
+                    }
+                }
+            }
+        }
+
+        rules
+    }
 
-                        if let Expr::Object(nested_obj) = &*kv.value {
-                            let mut nested_styles = HashMap::new();
+    /// Rejects `'@theme name'` blocks under global selectors other than the root element.
+    /// Theme selectors sit on the root, so `html` and `:root` compound with them; for any
+    /// other selector the theme would also apply inside scoped theme subtrees.
+    fn reject_global_themes(&self, selector: &str, obj: &ObjectLit, block: &mut StyleBlock) {
+        for prop in &obj.props {
+            if let PropOrSpread::Prop(prop) = prop {
+                if let Prop::KeyValue(kv) = &**prop {
+                    let key = self.extract_prop_key(&kv.key);
+
+                    let Some(theme_name) = key.strip_prefix("@theme ") else {
+                        continue;
+                    };
+
+                    if block.nested_selectors.remove(&key).is_some() {
+                        let themed: Vec<String> = self
+                            .config
+                            .theme_selectors(theme_name.trim())
+                            .iter()
+                            .map(|theme_selector| format!("{} {}", theme_selector, selector))
+                            .collect();
+
+                        emit_error(
+                            kv.key.span(),
+                            &StoopError::InvalidStyle(format!(
+                                "`{}` under `{}` is not supported in globalCss; only `html` and `:root` can be themed. Write the theme selector out instead, e.g. `'{}'`.",
+                                key,
+                                selector,
+                                themed.join(", "),
+                            )),
+                        );
+                    }
+                }
+            }
+        }
+    }
 
-                            for nested_prop in &nested_obj.props {
-                                if let PropOrSpread::Prop(np) = nested_prop {
-                                    if let Prop::KeyValue(nkv) = &**np {
-                                        let nested_key = self.extract_prop_key(&nkv.key);
-                                        let nested_value = self.extract_style_value(&nkv.value);
-                                        nested_styles.insert(nested_key, nested_value);
+    /// Extracts `createTheme('name', { colors: {...}, ... })` into a theme name and scales.
+    /// Returns `None` when the name is not a string literal.
+    pub fn extract_theme(&self, call: &CallExpr) -> Option<(String, Theme)> {
+        let name = match call.args.first().map(|arg| &*arg.expr) {
+            Some(Expr::Lit(Lit::Str(s))) => String::from_utf8_lossy(s.value.as_bytes()).into_owned(),
+            _ => return None,
+        };
+
+        let mut theme = Theme::default();
+
+        if let Some(Expr::Object(obj)) = call.args.get(1).map(|arg| &*arg.expr) {
+            for prop in &obj.props {
+                if let PropOrSpread::Prop(prop) = prop {
+                    if let Prop::KeyValue(kv) = &**prop {
+                        let scale_name = self.extract_prop_key(&kv.key);
+
+                        if let (Some(scale), Expr::Object(scale_obj)) =
+                            (theme.scale_mut(&scale_name), &*kv.value)
+                        {
+                            let mut tokens = HashMap::new();
+
+                            for token_prop in &scale_obj.props {
+                                if let PropOrSpread::Prop(tp) = token_prop {
+                                    if let Prop::KeyValue(tkv) = &**tp {
+                                        let value = match &*tkv.value {
+                                            Expr::Lit(Lit::Str(s)) => String::from_utf8_lossy(s.value.as_bytes()).into_owned(),
+                                            Expr::Lit(Lit::Num(n)) => n.value.to_string(),
+                                            _ => continue,
+                                        };
+                                        tokens.insert(self.extract_prop_key(&tkv.key), value);
                                     }
                                 }
                             }
 
-                            nested.insert(key, nested_styles);
+                            *scale = Some(tokens);
                         }
                     }
                 }
             }
         }
 
-        nested
+        Some((name, theme))
     }
 
     fn extract_variants(
         &self,
-        call: &CallExpr,
-    ) -> HashMap<String, HashMap<String, HashMap<String, StyleValue>>> {
+        arg: Option<&ExprOrSpread>,
+    ) -> HashMap<String, HashMap<String, StyleBlock>> {
         let mut variants = HashMap::new();
 
-        if call.args.len() < 3 {
-            return variants;
-        }
-
-        if let Expr::Object(obj) = &*call.args[2].expr {
+        if let Some(Expr::Object(obj)) = arg.map(|arg| &*arg.expr) {
             for prop in &obj.props {
                 if let PropOrSpread::Prop(prop) = prop {
                     if let Prop::KeyValue(kv) = &**prop {
                         let variant_name = self.extract_prop_key(&kv.key);
 
+                        if variant_name == "compoundVariants" || variant_name == "defaultVariants" {
+                            continue;
+                        }
+
                         if let Expr::Object(variant_obj) = &*kv.value {
                             let mut variant_values = HashMap::new();
 
@@ -173,19 +516,10 @@ impl StyleExtractor {
                                         let value_name = self.extract_prop_key(&vkv.key);
 
                                         if let Expr::Object(styles_obj) = &*vkv.value {
-                                            let mut styles = HashMap::new();
-
-                                            for style_prop in &styles_obj.props {
-                                                if let PropOrSpread::Prop(sp) = style_prop {
-                                                    if let Prop::KeyValue(skv) = &**sp {
-                                                        let style_key = self.extract_prop_key(&skv.key);
-                                                        let style_value = self.extract_style_value(&skv.value);
-                                                        styles.insert(style_key, style_value);
-                                                    }
-                                                }
-                                            }
-
-                                            variant_values.insert(value_name, styles);
+                                            variant_values.insert(
+                                                value_name,
+                                                self.extract_style_block(styles_obj),
+                                            );
                                         }
                                     }
                                 }
@@ -201,11 +535,138 @@ impl StyleExtractor {
         variants
     }
 
+    /// Extracts `compoundVariants: [{ size: 'lg', color: 'danger', css: {...} }]` from the
+    /// variants object. Entries that reference undeclared variants are reported and skipped.
+    fn extract_compound_variants(
+        &self,
+        arg: Option<&ExprOrSpread>,
+        variants: &HashMap<String, HashMap<String, StyleBlock>>,
+    ) -> Vec<CompoundVariant> {
+        let mut compound_variants = Vec::new();
+
+        let entries = match self.find_variant_option(arg, "compoundVariants") {
+            Some(Expr::Array(array)) => Some(array),
+            _ => None,
+        };
+
+        for elem in entries.into_iter().flat_map(|array| array.elems.iter().flatten()) {
+            let Expr::Object(entry) = &*elem.expr else {
+                continue;
+            };
+
+            let mut conditions = Vec::new();
+            let mut styles = StyleBlock::default();
+
+            for prop in &entry.props {
+                if let PropOrSpread::Prop(prop) = prop {
+                    if let Prop::KeyValue(kv) = &**prop {
+                        let key = self.extract_prop_key(&kv.key);
+
+                        match &*kv.value {
+                            Expr::Object(css_obj) if key == "css" => {
+                                styles = self.extract_style_block(css_obj);
+                            }
+                            Expr::Lit(Lit::Str(s)) => {
+                                conditions.push((key, String::from_utf8_lossy(s.value.as_bytes()).into_owned()));
+                            }
+                            Expr::Lit(Lit::Bool(b)) => conditions.push((key, b.value.to_string())),
+                            Expr::Lit(Lit::Num(n)) => conditions.push((key, n.value.to_string())),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            if let Some((unknown, _)) = conditions.iter().find(|(name, _)| !variants.contains_key(name)) {
+                emit_warning(
+                    entry.span,
+                    &StoopError::InvalidStyle(format!(
+                        "compound variant references undeclared variant `{}` and was skipped",
+                        unknown
+                    )),
+                );
+                continue;
+            }
+
+            if !conditions.is_empty() {
+                compound_variants.push(CompoundVariant { conditions, styles });
+            }
+        }
+
+        compound_variants
+    }
+
+    /// Extracts `defaultVariants: { size: 'md' }` from the variants object. Defaults that
+    /// name an undeclared variant or value are reported and skipped.
+    fn extract_default_variants(
+        &self,
+        arg: Option<&ExprOrSpread>,
+        variants: &HashMap<String, HashMap<String, StyleBlock>>,
+    ) -> HashMap<String, String> {
+        let mut defaults = HashMap::new();
+
+        let Some(Expr::Object(obj)) = self.find_variant_option(arg, "defaultVariants") else {
+            return defaults;
+        };
+
+        for prop in &obj.props {
+            if let PropOrSpread::Prop(prop) = prop {
+                if let Prop::KeyValue(kv) = &**prop {
+                    let variant_name = self.extract_prop_key(&kv.key);
+                    let value_name = match &*kv.value {
+                        Expr::Lit(Lit::Str(s)) => String::from_utf8_lossy(s.value.as_bytes()).into_owned(),
+                        Expr::Lit(Lit::Bool(b)) => b.value.to_string(),
+                        Expr::Lit(Lit::Num(n)) => n.value.to_string(),
+                        _ => continue,
+                    };
+
+                    // `false` is a valid default for boolean variants that only declare `true`
+                    let known = variants
+                        .get(&variant_name)
+                        .map(|values| {
+                            values.contains_key(&value_name)
+                                || (value_name == "false" && values.contains_key("true"))
+                        })
+                        .unwrap_or(false);
+
+                    if !known {
+                        emit_warning(
+                            kv.value.span(),
+                            &StoopError::InvalidStyle(format!(
+                                "default variant `{}: {}` does not match a declared variant value and was skipped",
+                                variant_name, value_name
+                            )),
+                        );
+                        continue;
+                    }
+
+                    defaults.insert(variant_name, value_name);
+                }
+            }
+        }
+
+        defaults
+    }
+
+    /// Finds a reserved option such as `compoundVariants` in the variants object
+    fn find_variant_option<'a>(&self, arg: Option<&'a ExprOrSpread>, name: &str) -> Option<&'a Expr> {
+        match arg.map(|arg| &*arg.expr) {
+            Some(Expr::Object(obj)) => obj.props.iter().find_map(|prop| match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::KeyValue(kv) if self.extract_prop_key(&kv.key) == name => Some(&*kv.value),
+                    _ => None,
+                },
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
     fn extract_prop_key(&self, key: &PropName) -> String {
         match key {
             PropName::Ident(ident) => {
                 // Atom -> &str
-                (&*ident.sym).to_owned()
+                (*ident.sym).to_owned()
             }
             PropName::Str(s) => {
                 // Wtf8Atom -> &Wtf8, need to use lossy conversion
@@ -220,73 +681,131 @@ impl StyleExtractor {
             Expr::Lit(Lit::Str(s)) => {
                 // Wtf8Atom -> &Wtf8, need to use lossy conversion
                 let value: String = String::from_utf8_lossy(s.value.as_bytes()).into_owned();
-
-                if value.starts_with('$') {
-                    StyleValue::Token(value)
-                } else if value.contains('$') {
-                    self.extract_compound_value(&value)
-                } else {
-                    StyleValue::Static(value)
-                }
-            }
-            Expr::Lit(Lit::Num(n)) => {
-                StyleValue::Static(n.value.to_string())
+                parse_style_string(value)
             }
+            Expr::Lit(Lit::Num(_)) | Expr::Unary(_) => match numeric_literal(expr) {
+                Some(number) => StyleValue::Static(number.to_string()),
+                None => StyleValue::Static(String::new()),
+            },
             Expr::Lit(Lit::Bool(b)) => {
                 StyleValue::Static(if b.value { "true".to_string() } else { "false".to_string() })
             }
             _ => StyleValue::Static(String::new()),
         }
     }
+}
 
-    fn extract_compound_value(&self, value: &str) -> StyleValue {
-        let mut parts = Vec::new();
-        let mut current = String::new();
-        let mut in_token = false;
-
-        // Improved token parsing that handles:
-        // - Tokens at start: "$md"
-        // - Tokens in middle: "calc($md + 10px)"
-        // - Multiple tokens: "$md $lg"
-        // - Tokens with dots: "$colors.primary"
-
-        let mut chars = value.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '$' {
-                // Save current static part if any
-                if !current.is_empty() {
-                    parts.push(StylePart::Static(current.clone()));
-                    current.clear();
-                }
-                in_token = true;
-                current.push(ch);
-            } else if in_token {
-                // Continue token until we hit whitespace, comma, or closing paren
-                if ch.is_whitespace() || ch == ',' || ch == ')' {
-                    if !current.is_empty() {
-                        parts.push(StylePart::Token(current.clone()));
-                        current.clear();
-                    }
-                    in_token = false;
-                    current.push(ch);
-                } else {
-                    current.push(ch);
-                }
-            } else {
-                current.push(ch);
-            }
+/// A number literal, including negative ones like `-8`, which parse as unary minus
+fn numeric_literal(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Lit(Lit::Num(n)) => Some(n.value),
+        Expr::Unary(UnaryExpr { op: UnaryOp::Minus, arg, .. }) => match &**arg {
+            Expr::Lit(Lit::Num(n)) => Some(-n.value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Keys that open a nested selector or at-rule rather than a declaration
+fn is_nested_key(key: &str) -> bool {
+    key.starts_with('&') || key.starts_with(':') || key.starts_with('@')
+}
+
+/// At-rules whose body is a list of declarations rather than nested rules
+fn is_declaration_at_rule(key: &str) -> bool {
+    ["@font-face", "@page", "@property", "@counter-style", "@font-palette-values"]
+        .iter()
+        .any(|name| key == *name || key.starts_with(&format!("{} ", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swc_core::common::{sync::Lrc, FileName, SourceMap, GLOBALS};
+    use swc_core::ecma::parser::{parse_file_as_expr, Syntax};
+
+    fn parse_object(source: &str) -> ObjectLit {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(Lrc::new(FileName::Anon), format!("({})", source));
+        let expr = parse_file_as_expr(&fm, Syntax::Es(Default::default()), Default::default(), None, &mut vec![])
+            .expect("Failed to parse");
+
+        match *expr {
+            Expr::Paren(ParenExpr { expr, .. }) => match *expr {
+                Expr::Object(obj) => obj,
+                _ => panic!("expected an object literal"),
+            },
+            _ => panic!("expected an object literal"),
         }
+    }
 
-        // Add remaining part
-        if !current.is_empty() {
-            if in_token {
-                parts.push(StylePart::Token(current));
-            } else {
-                parts.push(StylePart::Static(current));
-            }
+    fn static_value(value: Option<&StyleValue>) -> &str {
+        match value {
+            Some(StyleValue::Static(value)) => value,
+            other => panic!("expected a static value, got {:?}", other),
         }
+    }
+
+    #[test]
+    fn test_negative_numbers() {
+        GLOBALS.set(&Default::default(), || {
+            let extractor = StyleExtractor::new(&StoopConfig::default());
+
+            let obj = parse_object("{ marginTop: -8, mx: -4, zIndex: -1, opacity: 0.5 }");
+            assert!(extractor.is_static_style_object(&obj));
+
+            let block = extractor.extract_style_block(&obj);
+            assert_eq!(static_value(block.styles.get("marginTop")), "-8px");
+            assert_eq!(static_value(block.styles.get("marginLeft")), "-4px");
+            assert_eq!(static_value(block.styles.get("marginRight")), "-4px");
+            assert_eq!(static_value(block.styles.get("zIndex")), "-1");
+            assert_eq!(static_value(block.styles.get("opacity")), "0.5");
+
+            assert!(!extractor.is_static_style_object(&parse_object("{ marginTop: -offset }")));
+        });
+    }
+
+    #[test]
+    fn test_kebab_keys() {
+        GLOBALS.set(&Default::default(), || {
+            let extractor = StyleExtractor::new(&StoopConfig::default());
+            let block = extractor.extract_style_block(&parse_object(
+                "{ 'background-color': 'red', '-webkit-line-clamp': 2, '--ring-color': 'blue' }",
+            ));
+
+            assert_eq!(static_value(block.styles.get("backgroundColor")), "red");
+            assert_eq!(static_value(block.styles.get("WebkitLineClamp")), "2");
+            assert_eq!(static_value(block.styles.get("--ring-color")), "blue");
+        });
+    }
+
+    #[test]
+    fn test_global_themes_only_on_root() {
+        GLOBALS.set(&Default::default(), || {
+            let config = StoopConfig {
+                themes: Some(HashMap::from([("dark".to_string(), Theme::default())])),
+                ..StoopConfig::default()
+            };
+            let extractor = StyleExtractor::new(&config);
+
+            let rules = extractor.extract_global_rules(&parse_object(
+                "{ html: { '@theme dark': { colorScheme: 'dark' } }, body: { '@theme dark': { color: 'white' }, '&:hover': { color: 'red' } } }",
+            ));
+
+            let nested: Vec<Vec<&String>> = rules
+                .iter()
+                .map(|rule| match rule {
+                    GlobalRule::Selector { block, .. } => {
+                        let mut keys: Vec<&String> = block.nested_selectors.keys().collect();
+                        keys.sort();
+                        keys
+                    }
+                    other => panic!("expected a selector rule, got {:?}", other),
+                })
+                .collect();
 
-        StyleValue::Compound(parts)
+            assert_eq!(nested, vec![vec!["@theme dark"], vec!["&:hover"]]);
+        });
     }
 }
@@ -1,23 +1,43 @@
 use std::collections::HashMap;
-use crate::config::StoopConfig;
-use crate::types::{StyleExtraction, StyleValue, StylePart, CSSOutput};
-use crate::hasher::ClassNameHasher;
-use crate::tokens::TokenResolver;
+use crate::config::{is_theme_root, StoopConfig, TokenOutput};
+use crate::types::{GlobalRule, StyleExtraction, StyleValue, StylePart, CSSOutput};
+use crate::hasher::{normalize_name, Case, ClassNameHasher};
+use crate::tokens::{shorthand_slots, TokenResolver, VariableNamer};
 
 const BASE_INDENT: usize = 2;
-const NESTED_INDENT: usize = 4;
 
 pub struct CSSGenerator {
     config: StoopConfig,
     hasher: ClassNameHasher,
     token_resolver: TokenResolver,
+    /// Resolvers for each alternate theme, only used with inline `tokenOutput`
+    theme_resolvers: Vec<(String, TokenResolver)>,
 }
 
 impl CSSGenerator {
     pub fn new(config: &StoopConfig) -> Self {
+        let variables = VariableNamer::new(config);
+        let mut theme_resolvers = Vec::new();
+
+        if config.token_output == TokenOutput::Inline {
+            if let Some(themes) = &config.themes {
+                let mut names: Vec<_> = themes.keys().collect();
+                names.sort();
+
+                for name in names {
+                    let theme = config.theme.merged_with(&themes[name]);
+                    theme_resolvers.push((
+                        name.clone(),
+                        TokenResolver::with_output(&theme, &variables, TokenOutput::Inline),
+                    ));
+                }
+            }
+        }
+
         Self {
             hasher: ClassNameHasher::new(),
-            token_resolver: TokenResolver::new(&config.theme),
+            token_resolver: TokenResolver::with_output(&config.theme, &variables, config.token_output),
+            theme_resolvers,
             config: config.clone(),
         }
     }
@@ -27,14 +47,16 @@ impl CSSGenerator {
             base_class: String::new(),
             selector_class: String::new(),
             variant_classes: HashMap::new(),
+            compound_variant_classes: Vec::new(),
             css: String::new(),
         };
 
         if !extraction.base_styles.is_empty() || !extraction.nested_selectors.is_empty() {
-            output.base_class = self.hasher.hash_styles(
+            output.base_class = self.hasher.hash_block(
                 &extraction.component_name,
                 "base",
                 &extraction.base_styles,
+                &extraction.nested_selectors,
             );
 
             output.base_class = format!("{}-{}", self.config.prefix, output.base_class);
@@ -52,21 +74,43 @@ impl CSSGenerator {
             output.css.push('\n');
         }
 
+        // Media-wrapped copies of every variant value, one per breakpoint in `config.media`,
+        // selected at runtime by responsive props like `size={{ '@initial': 'sm', '@bp2': 'lg' }}`.
+        // Rules are grouped per breakpoint in configured order, so later breakpoints win.
+        let breakpoints = self.config.breakpoint_names();
+        let mut responsive_rules = vec![String::new(); breakpoints.len()];
+
         for (variant_name, variant_values) in &extraction.variants {
             let mut variant_class_map = HashMap::new();
 
-            for (value_name, styles) in variant_values {
-                let class_name = self.hasher.hash_styles(
+            for (value_name, block) in variant_values {
+                let class_name = self.hasher.hash_block(
                     &extraction.component_name,
                     &format!("{}-{}", variant_name, value_name),
-                    styles,
+                    &block.styles,
+                    &block.nested_selectors,
                 );
 
                 let full_class_name = format!("{}-{}", self.config.prefix, class_name);
-                let variant_css = self.generate_rule(&full_class_name, styles);
+                let variant_css = self.generate_selector_rule(
+                    &format!(".{}", full_class_name),
+                    &block.styles,
+                    &block.nested_selectors,
+                );
 
                 output.css.push_str(&variant_css);
-                output.css.push('\n');
+
+                for (breakpoint, rules) in breakpoints.iter().zip(&mut responsive_rules) {
+                    let breakpoint_rule = self.generate_selector_rule(
+                        &format!(".{}-{}", full_class_name, breakpoint),
+                        &block.styles,
+                        &block.nested_selectors,
+                    );
+                    rules.push_str(&self.wrap_at_rule(
+                        &self.resolve_at_rule(&format!("@{}", breakpoint)),
+                        &breakpoint_rule,
+                    ));
+                }
 
                 variant_class_map.insert(value_name.clone(), full_class_name);
             }
@@ -74,6 +118,40 @@ impl CSSGenerator {
             output.variant_classes.insert(variant_name.clone(), variant_class_map);
         }
 
+        for rules in &responsive_rules {
+            output.css.push_str(rules);
+        }
+
+        // Compound variants come after single-variant rules so they win on equal specificity
+        for compound in &extraction.compound_variants {
+            let variant_key = compound
+                .conditions
+                .iter()
+                .map(|(name, value)| format!("{}-{}", name, value))
+                .collect::<Vec<_>>()
+                .join("-");
+
+            let class_name = self.hasher.hash_block(
+                &extraction.component_name,
+                &format!("compound-{}", variant_key),
+                &compound.styles.styles,
+                &compound.styles.nested_selectors,
+            );
+
+            let full_class_name = format!("{}-{}", self.config.prefix, class_name);
+            let compound_css = self.generate_selector_rule(
+                &format!(".{}", full_class_name),
+                &compound.styles.styles,
+                &compound.styles.nested_selectors,
+            );
+
+            output.css.push_str(&compound_css);
+
+            output
+                .compound_variant_classes
+                .push((compound.conditions.clone(), full_class_name));
+        }
+
         output
     }
 
@@ -82,101 +160,228 @@ impl CSSGenerator {
         class_name: &str,
         base_styles: &HashMap<String, StyleValue>,
         nested_selectors: &HashMap<String, HashMap<String, StyleValue>>,
+    ) -> String {
+        self.generate_selector_rule(&format!(".{}", class_name), base_styles, nested_selectors)
+    }
+
+    fn generate_selector_rule(
+        &self,
+        selector: &str,
+        styles: &HashMap<String, StyleValue>,
+        nested_selectors: &HashMap<String, HashMap<String, StyleValue>>,
     ) -> String {
         let mut css = String::new();
 
         // Generate base styles
-        if !base_styles.is_empty() {
-            css.push_str(&format!(".{} {{\n", class_name));
-            self.write_properties(&mut css, base_styles, BASE_INDENT);
-            css.push_str("}\n\n");
+        if !styles.is_empty() {
+            self.write_rule(&mut css, selector, styles);
         }
 
         // Generate nested selectors
-        for (selector, styles) in nested_selectors {
-            if selector.starts_with('@') {
+        for (nested, nested_styles) in nested_selectors {
+            if let Some(theme_name) = nested.strip_prefix("@theme ") {
+                css.push_str(&self.generate_theme_rule(selector, theme_name.trim(), nested_styles));
+            } else if nested.starts_with('@') {
                 // Media query - wrap entire rule
-                css.push_str(&format!("{} {{\n", selector));
-                css.push_str(&format!("  .{} {{\n", class_name));
-                self.write_properties(&mut css, styles, NESTED_INDENT);
-                css.push_str("  }}\n");
-                css.push_str("}}\n\n");
+                let mut rule = String::new();
+                self.write_rule(&mut rule, selector, nested_styles);
+                css.push_str(&self.wrap_at_rule(&self.resolve_at_rule(nested), &rule));
             } else {
-                let full_selector = self.build_nested_selector(class_name, selector);
-                css.push_str(&format!("{} {{\n", full_selector));
-                self.write_properties(&mut css, styles, BASE_INDENT);
-                css.push_str("}\n\n");
+                let full_selector = self.build_nested_selector(selector, nested);
+                self.write_rule(&mut css, &full_selector, nested_styles);
             }
         }
 
         css
     }
 
-    fn write_properties(
-        &self,
-        css: &mut String,
-        styles: &HashMap<String, StyleValue>,
-        indent: usize,
-    ) {
-        let indent_str = " ".repeat(indent);
-        for (prop, value) in styles {
-            let css_prop = self.js_to_css_property(prop);
-            let css_value = self.style_value_to_css(value, prop);
-            css.push_str(&format!("{}{}: {};\n", indent_str, css_prop, css_value));
+    /// Writes one rule. With inline `tokenOutput` it is followed by an override rule per
+    /// alternate theme, holding the declarations whose tokens differ under that theme
+    fn write_rule(&self, css: &mut String, selector: &str, styles: &HashMap<String, StyleValue>) {
+        css.push_str(&format!("{} {{\n", selector));
+        self.write_properties(css, styles, BASE_INDENT);
+        css.push_str("}\n\n");
+
+        for (theme_name, resolver) in &self.theme_resolvers {
+            let overrides: HashMap<String, StyleValue> = styles
+                .iter()
+                .filter(|(prop, value)| {
+                    self.resolve_value(resolver, value, prop)
+                        != self.resolve_value(&self.token_resolver, value, prop)
+                })
+                .map(|(prop, value)| (prop.clone(), value.clone()))
+                .collect();
+
+            if !overrides.is_empty() {
+                css.push_str(&self.generate_theme_rule(selector, theme_name, &overrides));
+            }
         }
     }
 
-    fn generate_rule(
+    /// Generates a `'@theme dark'` block, which applies under the theme's selectors from
+    /// `themeSelector`, e.g. `[data-theme="dark"] .cls`, or on them for the root element,
+    /// e.g. `html[data-theme="dark"]`
+    fn generate_theme_rule(
         &self,
-        class_name: &str,
+        selector: &str,
+        theme_name: &str,
         styles: &HashMap<String, StyleValue>,
     ) -> String {
-        let mut rule = format!(".{} {{\n", class_name);
-        self.write_properties(&mut rule, styles, BASE_INDENT);
-        rule.push_str("}\n");
-        rule
-    }
+        let mut css = String::new();
 
-    fn build_nested_selector(&self, base_class: &str, selector: &str) -> String {
-        // Note: Media queries are handled separately in generate_base_rule
-        match selector.chars().next() {
-            Some('&') => selector.replacen('&', &format!(".{}", base_class), 1),
-            Some(':') => format!(".{}{}", base_class, selector),
-            _ => format!(".{} {}", base_class, selector),
+        // Inline values have to come from the theme itself rather than its variables
+        let resolver = self
+            .theme_resolvers
+            .iter()
+            .find(|(name, _)| name == theme_name)
+            .map_or(&self.token_resolver, |(_, resolver)| resolver);
+
+        let is_root = is_theme_root(selector);
+
+        let selectors: Vec<String> = self
+            .config
+            .theme_selectors(theme_name)
+            .iter()
+            .map(|theme_selector| {
+                if is_root {
+                    format!("{}{}", selector, theme_selector)
+                } else {
+                    format!("{} {}", theme_selector, selector)
+                }
+            })
+            .collect();
+
+        if !selectors.is_empty() {
+            css.push_str(&format!("{} {{\n", selectors.join(", ")));
+            self.write_resolved_properties(&mut css, styles, BASE_INDENT, resolver);
+            css.push_str("}\n\n");
+        }
+
+        if let Some(media_query) = self.config.theme_media_query(theme_name) {
+            let unthemed = self.config.unthemed_root_selector();
+            let rule_selector = if is_root {
+                format!("{}{}", selector, unthemed.trim_start_matches(":root"))
+            } else {
+                format!("{} {}", unthemed, selector)
+            };
+            let mut rule = format!("{} {{\n", rule_selector);
+            self.write_resolved_properties(&mut rule, styles, BASE_INDENT, resolver);
+            rule.push_str("}\n");
+            css.push_str(&self.wrap_at_rule(&media_query, &rule));
         }
+
+        css
     }
 
-    fn js_to_css_property(&self, prop: &str) -> String {
-        let mut result = String::new();
+    /// Generates the stylesheet for the rules of a `globalCss()` call
+    pub fn generate_global(&self, rules: &[GlobalRule]) -> String {
+        let mut css = String::new();
 
-        for (i, ch) in prop.chars().enumerate() {
-            if ch.is_uppercase() {
-                if i > 0 {
-                    result.push('-');
+        for rule in rules {
+            match rule {
+                GlobalRule::Selector { selector, block } => {
+                    css.push_str(&self.generate_selector_rule(
+                        selector,
+                        &block.styles,
+                        &block.nested_selectors,
+                    ));
                 }
-                result.push(ch.to_lowercase().next().unwrap());
-            } else {
-                result.push(ch);
+                GlobalRule::AtRule { name, blocks } => {
+                    for styles in blocks {
+                        css.push_str(&format!("{} {{\n", name));
+                        self.write_properties(&mut css, styles, BASE_INDENT);
+                        css.push_str("}\n\n");
+                    }
+                }
+                GlobalRule::Group { query, rules } => {
+                    let inner = self.generate_global(rules);
+                    css.push_str(&self.wrap_at_rule(&self.resolve_at_rule(query), &inner));
+                }
+            }
+        }
+
+        css
+    }
+
+    /// Wraps generated rules in an at-rule block, indenting them one level
+    fn wrap_at_rule(&self, at_rule: &str, inner: &str) -> String {
+        let mut css = format!("{} {{\n", at_rule);
+
+        for line in inner.trim_end().lines() {
+            if !line.is_empty() {
+                css.push_str("  ");
             }
+            css.push_str(line);
+            css.push('\n');
+        }
+
+        css.push_str("}\n\n");
+        css
+    }
+
+    /// Expands breakpoint aliases from `config.media` (`@bp1`) into full at-rules
+    fn resolve_at_rule(&self, key: &str) -> String {
+        let alias = key.trim_start_matches('@');
+
+        match self.config.media.as_ref().and_then(|media| media.get(alias)) {
+            Some(query) if query.starts_with('@') => query.clone(),
+            Some(query) => format!("@media {}", query),
+            None => key.to_string(),
+        }
+    }
+
+    fn write_properties(
+        &self,
+        css: &mut String,
+        styles: &HashMap<String, StyleValue>,
+        indent: usize,
+    ) {
+        self.write_resolved_properties(css, styles, indent, &self.token_resolver);
+    }
+
+    fn write_resolved_properties(
+        &self,
+        css: &mut String,
+        styles: &HashMap<String, StyleValue>,
+        indent: usize,
+        resolver: &TokenResolver,
+    ) {
+        let indent_str = " ".repeat(indent);
+        for (prop, value) in styles {
+            let css_prop = normalize_name(prop, Case::Kebab);
+            let css_value = self.resolve_value(resolver, value, prop);
+            css.push_str(&format!("{}{}: {};\n", indent_str, css_prop, css_value));
         }
+    }
 
-        result
+    fn build_nested_selector(&self, parent: &str, selector: &str) -> String {
+        // Note: Media queries are handled separately in generate_selector_rule
+        match selector.chars().next() {
+            Some('&') => selector.replacen('&', parent, 1),
+            Some(':') => format!("{}{}", parent, selector),
+            _ => format!("{} {}", parent, selector),
+        }
     }
 
-    fn style_value_to_css(&self, value: &StyleValue, property: &str) -> String {
+    fn resolve_value(&self, resolver: &TokenResolver, value: &StyleValue, property: &str) -> String {
         match value {
             StyleValue::Static(s) => s.clone(),
             StyleValue::Token(token) => {
-                self.token_resolver.resolve(token, property)
+                resolver.resolve(token, property)
             }
             StyleValue::Compound(parts) => {
                 let mut result = String::new();
+                let slots = shorthand_slots(parts, property);
+                let mut token_index = 0;
 
                 for part in parts {
                     match part {
                         StylePart::Static(s) => result.push_str(s),
                         StylePart::Token(token) => {
-                            let resolved = self.token_resolver.resolve(token, property);
+                            let slot = slots.as_ref().and_then(|slots| slots.get(token_index).copied());
+                            token_index += 1;
+
+                            let resolved = resolver.resolve_part(token, property, slot);
                             result.push_str(&resolved);
                         }
                     }
@@ -187,3 +392,45 @@ impl CSSGenerator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Theme;
+    use crate::types::StyleBlock;
+
+    fn themed_config() -> StoopConfig {
+        let mut config = StoopConfig {
+            themes: Some(HashMap::from([("dark".to_string(), Theme::default())])),
+            ..StoopConfig::default()
+        };
+        config.theme_selector.media = true;
+        config
+    }
+
+    fn global_rule(selector: &str) -> GlobalRule {
+        GlobalRule::Selector {
+            selector: selector.to_string(),
+            block: StyleBlock {
+                styles: HashMap::new(),
+                nested_selectors: HashMap::from([(
+                    "@theme dark".to_string(),
+                    HashMap::from([("colorScheme".to_string(), StyleValue::Static("dark".to_string()))]),
+                )]),
+            },
+        }
+    }
+
+    #[test]
+    fn test_global_theme_on_root() {
+        let generator = CSSGenerator::new(&themed_config());
+
+        let css = generator.generate_global(&[global_rule("html")]);
+        assert!(css.contains("html[data-theme=\"dark\"] {\n  color-scheme: dark;"), "{}", css);
+        assert!(css.contains("html:not([data-theme]) {"), "{}", css);
+
+        let css = generator.generate_global(&[global_rule(":root")]);
+        assert!(css.contains(":root[data-theme=\"dark\"] {"), "{}", css);
+        assert!(css.contains(":root:not([data-theme]) {"), "{}", css);
+    }
+}
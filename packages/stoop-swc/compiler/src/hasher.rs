@@ -5,16 +5,25 @@ use crate::types::StyleValue;
 
 pub struct ClassNameHasher;
 
+impl Default for ClassNameHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ClassNameHasher {
     pub fn new() -> Self {
         Self
     }
 
-    pub fn hash_styles(
+    /// Hashes a style block, including nested selectors so blocks that only
+    /// differ in their nested rules get distinct class names
+    pub fn hash_block(
         &self,
         component_name: &str,
         variant_key: &str,
         styles: &HashMap<String, StyleValue>,
+        nested_selectors: &HashMap<String, HashMap<String, StyleValue>>,
     ) -> String {
         let mut hasher = DefaultHasher::new();
 
@@ -31,6 +40,22 @@ impl ClassNameHasher {
             }
         }
 
+        let mut selectors: Vec<_> = nested_selectors.keys().collect();
+        selectors.sort();
+
+        for selector in selectors {
+            selector.hash(&mut hasher);
+            if let Some(nested_styles) = nested_selectors.get(selector) {
+                let mut nested_keys: Vec<_> = nested_styles.keys().collect();
+                nested_keys.sort();
+
+                for key in nested_keys {
+                    key.hash(&mut hasher);
+                    self.hash_style_value(&nested_styles[key], &mut hasher);
+                }
+            }
+        }
+
         let hash = hasher.finish();
         let component_kebab = self.to_kebab_case(component_name);
         let variant_kebab = self.to_kebab_case(variant_key);
@@ -53,6 +78,9 @@ impl ClassNameHasher {
         }
     }
 
+    /// Kebab-cases a component name or variant key for a class name. Unlike
+    /// `normalize_name`, it knows nothing of vendor prefixes, and runs of capitals stay
+    /// one word: `OAuthButton` -> `oauth-button`.
     fn to_kebab_case(&self, input: &str) -> String {
         let mut result = String::new();
         let mut prev_was_upper = false;
@@ -72,6 +100,88 @@ impl ClassNameHasher {
 
         result
     }
+
+}
+
+/// Spelling of a style key, scale or token name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    /// CSS spelling: `backgroundColor` -> `background-color`, `WebkitAppearance` -> `-webkit-appearance`
+    Kebab,
+    /// JS spelling: `background-color` -> `backgroundColor`, `-ms-flex` -> `msFlex`
+    Camel,
+}
+
+/// Vendor prefixes in their JS spelling, as React writes them
+pub const VENDOR_PREFIXES: &[(&str, &str)] = &[("Webkit", "webkit"), ("Moz", "moz"), ("ms", "ms"), ("Ms", "ms"), ("O", "o")];
+
+/// Converts a name between its JS and CSS spellings. Every style key, scale name and
+/// design token path conversion goes through here; class names use
+/// `ClassNameHasher::to_kebab_case`. Custom properties (`--ring-color`) are kept verbatim, as are names
+/// already in the requested spelling.
+pub fn normalize_name(name: &str, case: Case) -> String {
+    if name.starts_with("--") {
+        return name.to_string();
+    }
+
+    match case {
+        Case::Kebab => {
+            if name.contains('-') {
+                return name.to_string();
+            }
+
+            let (mut result, rest) = VENDOR_PREFIXES
+                .iter()
+                .find_map(|(prefix, vendor)| {
+                    name.strip_prefix(prefix)
+                        .filter(|rest| rest.starts_with(|ch: char| ch.is_ascii_uppercase()))
+                        .map(|rest| (format!("-{}", vendor), rest))
+                })
+                .unwrap_or((String::new(), name));
+
+            for ch in rest.chars() {
+                if ch.is_uppercase() {
+                    if !result.is_empty() {
+                        result.push('-');
+                    }
+                    result.extend(ch.to_lowercase());
+                } else {
+                    result.push(ch);
+                }
+            }
+
+            result
+        }
+        Case::Camel => {
+            let mut words = name.split(['-', '_', ' ']).filter(|word| !word.is_empty()).peekable();
+            let mut result = String::new();
+
+            // `-webkit-line-clamp` -> `WebkitLineClamp`, but `-ms-flex` -> `msFlex`
+            if name.starts_with('-') {
+                if let Some((prefix, _)) = words
+                    .peek()
+                    .and_then(|first| VENDOR_PREFIXES.iter().find(|(_, vendor)| vendor == first))
+                {
+                    result.push_str(if *prefix == "Ms" { "ms" } else { prefix });
+                    words.next();
+                }
+            }
+
+            for word in words {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    if result.is_empty() {
+                        result.push(first);
+                    } else {
+                        result.extend(first.to_uppercase());
+                    }
+                    result.push_str(chars.as_str());
+                }
+            }
+
+            result
+        }
+    }
 }
 
 #[cfg(test)]
@@ -83,5 +193,42 @@ mod tests {
         let hasher = ClassNameHasher::new();
         assert_eq!(hasher.to_kebab_case("Button"), "button");
         assert_eq!(hasher.to_kebab_case("PrimaryButton"), "primary-button");
+        assert_eq!(hasher.to_kebab_case("OAuthButton"), "oauth-button");
+        assert_eq!(hasher.to_kebab_case("HTMLButton"), "htmlbutton");
+        assert_eq!(hasher.to_kebab_case("isActive-true"), "is-active-true");
+    }
+
+    #[test]
+    fn test_scale_names() {
+        assert_eq!(normalize_name("fontSizes", Case::Kebab), "font-sizes");
+        assert_eq!(normalize_name("colors", Case::Kebab), "colors");
+    }
+
+    #[test]
+    fn test_js_to_css_property() {
+        assert_eq!(normalize_name("backgroundColor", Case::Kebab), "background-color");
+        assert_eq!(normalize_name("color", Case::Kebab), "color");
+        assert_eq!(normalize_name("WebkitAppearance", Case::Kebab), "-webkit-appearance");
+        assert_eq!(normalize_name("MozOsxFontSmoothing", Case::Kebab), "-moz-osx-font-smoothing");
+        assert_eq!(normalize_name("msFlex", Case::Kebab), "-ms-flex");
+        assert_eq!(normalize_name("OTransition", Case::Kebab), "-o-transition");
+        assert_eq!(normalize_name("order", Case::Kebab), "order");
+        assert_eq!(normalize_name("--ring-color", Case::Kebab), "--ring-color");
+        assert_eq!(normalize_name("--ringColor", Case::Kebab), "--ringColor");
+        assert_eq!(normalize_name("background-color", Case::Kebab), "background-color");
+        assert_eq!(normalize_name("-webkit-line-clamp", Case::Kebab), "-webkit-line-clamp");
+    }
+
+    #[test]
+    fn test_camel_case() {
+        assert_eq!(normalize_name("background-color", Case::Camel), "backgroundColor");
+        assert_eq!(normalize_name("backgroundColor", Case::Camel), "backgroundColor");
+        assert_eq!(normalize_name("-webkit-line-clamp", Case::Camel), "WebkitLineClamp");
+        assert_eq!(normalize_name("-ms-flex", Case::Camel), "msFlex");
+        assert_eq!(normalize_name("--ring-color", Case::Camel), "--ring-color");
+        assert_eq!(normalize_name("z-indices", Case::Camel), "zIndices");
+        assert_eq!(normalize_name(&normalize_name("letterSpacings", Case::Kebab), Case::Camel), "letterSpacings");
+        assert_eq!(normalize_name("brand-primary_dark", Case::Camel), "brandPrimaryDark");
+        assert_eq!(normalize_name("blue-500", Case::Camel), "blue500");
     }
 }
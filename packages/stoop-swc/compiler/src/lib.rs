@@ -2,19 +2,26 @@ use swc_core::ecma::{
     ast::Program,
     visit::VisitMutWith,
 };
-use swc_core::plugin::{plugin_transform, proxies::TransformPluginProgramMetadata};
+use swc_core::plugin::{
+    metadata::TransformPluginMetadataContextKind,
+    plugin_transform,
+    proxies::TransformPluginProgramMetadata,
+};
 
-mod visitor;
-mod extractor;
-mod generator;
-mod hasher;
-mod transformer;
-mod writer;
-mod config;
-mod tokens;
-mod types;
-mod error;
-mod utils;
+pub mod visitor;
+pub mod extractor;
+pub mod generator;
+pub mod hasher;
+pub mod transformer;
+pub mod writer;
+pub mod config;
+pub mod tokens;
+pub mod types;
+pub mod error;
+pub mod utils;
+pub mod evaluator;
+pub mod design_tokens;
+pub mod units;
 
 use visitor::StoopVisitor;
 use config::StoopConfig;
@@ -25,7 +32,8 @@ pub fn process_transform(
     metadata: TransformPluginProgramMetadata,
 ) -> Program {
     let config = StoopConfig::from_metadata(&metadata);
-    let mut visitor = StoopVisitor::new(config);
+    let source_file = metadata.get_context(&TransformPluginMetadataContextKind::Filename);
+    let mut visitor = StoopVisitor::new(config, source_file);
 
     program.visit_mut_with(&mut visitor);
     visitor.finalize();
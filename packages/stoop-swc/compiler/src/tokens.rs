@@ -1,20 +1,236 @@
-use std::collections::HashMap;
-use crate::config::Theme;
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use crate::config::{StoopConfig, Theme, TokenOutput, VariableNaming};
+use crate::hasher::{normalize_name, Case};
+use crate::types::{StylePart, StyleValue};
+
+/// Shorthand properties whose values mix colors and lengths, e.g. `1px solid $primary`
+const SHORTHAND_PROPERTIES: &[&str] = &[
+    "border",
+    "borderTop",
+    "borderRight",
+    "borderBottom",
+    "borderLeft",
+    "outline",
+    "boxShadow",
+    "textDecoration",
+];
+
+/// Scales a token inside a shorthand can resolve against, tried in this order when the
+/// token isn't defined in its slot's own scales
+const SHORTHAND_SCALES: &[&str] = &["colors", "space", "sizes"];
+
+/// Scales a negated token can come from when its property has no scale of its own,
+/// tried in this order. Only space and sizes tokens are actually negated.
+const NUMERIC_SCALES: &[&str] = &["space", "sizes", "radii", "font-sizes", "letter-spacings"];
+
+/// Where a token sits in a shorthand value like `1px solid $primary`, which decides the
+/// scales it resolves against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    /// A leading numeric part: the width of `border`/`outline`/`textDecoration`, or the
+    /// offsets, blur and spread of `boxShadow`
+    Length,
+    /// Any later part, where the color goes
+    Color,
+}
+
+impl Slot {
+    fn scales(self) -> &'static [&'static str] {
+        match self {
+            Slot::Length => &["space", "sizes"],
+            Slot::Color => &["colors"],
+        }
+    }
+}
+
+/// Token references nested deeper than this are written as plain `var()` when inlining
+/// values, which also stops reference cycles (reported separately by the writer)
+const MAX_INLINE_DEPTH: usize = 16;
+
+/// Hex digits in a hashed variable name, unless its token collides with another
+const HASH_DIGITS: usize = 6;
+
+/// Names the CSS variable behind each token, namespaced by `config.prefix`:
+/// `--stoop-colors-primary`, or `--stoop-1a2b3c` with hashed `variableNames`
+#[derive(Debug, Clone)]
+pub struct VariableNamer {
+    prefix: String,
+    naming: VariableNaming,
+    /// Hex digits for hashed tokens whose six-digit name collides with another token's,
+    /// keyed by (CSS scale name, token)
+    lengthened: HashMap<(String, String), usize>,
+}
+
+impl VariableNamer {
+    pub fn new(config: &StoopConfig) -> Self {
+        let mut namer = Self {
+            prefix: config.prefix.clone(),
+            naming: config.variable_names,
+            lengthened: HashMap::new(),
+        };
+
+        if namer.naming == VariableNaming::Hashed {
+            let themes = std::iter::once(&config.theme).chain(config.themes.iter().flat_map(|themes| themes.values()));
+            let mut tokens: Vec<(String, String)> = themes
+                .flat_map(|theme| theme.scales())
+                .flat_map(|(scale, tokens)| tokens.keys().map(move |key| (scale.to_string(), key.clone())))
+                .collect();
+            tokens.sort();
+            tokens.dedup();
+
+            namer.lengthened = Self::lengthen_collisions(&tokens);
+        }
+
+        namer
+    }
+
+    /// The variable for `key` in a scale, given by its CSS name (`font-sizes`)
+    pub fn token(&self, scale: &str, key: &str) -> String {
+        match self.naming {
+            VariableNaming::Readable => self.prefixed(&format!("{}-{}", scale, key)),
+            VariableNaming::Hashed => {
+                let digits = self
+                    .lengthened
+                    .get(&(scale.to_string(), key.to_string()))
+                    .copied()
+                    .unwrap_or(HASH_DIGITS);
+                self.prefixed(&Self::hashed_name(Self::token_hash(scale, key), digits))
+            }
+        }
+    }
+
+    /// Gives every group of tokens sharing a hashed name two more digits, until each
+    /// name is unique. Tokens without a collision keep the short name.
+    fn lengthen_collisions(tokens: &[(String, String)]) -> HashMap<(String, String), usize> {
+        let mut lengthened = HashMap::new();
+        // Indexes into `tokens`, with their hashes
+        let mut pending: Vec<(usize, u64)> = tokens
+            .iter()
+            .enumerate()
+            .map(|(index, (scale, key))| (index, Self::token_hash(scale, key)))
+            .collect();
+        let mut digits = HASH_DIGITS;
+
+        while !pending.is_empty() && digits < 16 {
+            let mut groups: HashMap<String, Vec<(usize, u64)>> = HashMap::new();
+            for (index, hash) in pending {
+                groups.entry(Self::hashed_name(hash, digits)).or_default().push((index, hash));
+            }
+
+            pending = Vec::new();
+            for group in groups.into_values() {
+                if group.len() > 1 {
+                    pending.extend(group);
+                } else if digits > HASH_DIGITS {
+                    lengthened.insert(tokens[group[0].0].clone(), digits);
+                }
+            }
+
+            digits += 2;
+        }
+
+        for (index, _) in pending {
+            lengthened.insert(tokens[index].clone(), 16);
+        }
+
+        lengthened
+    }
+
+    fn token_hash(scale: &str, key: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        scale.hash(&mut hasher);
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Six-digit names are unpadded, as they always were; lengthened names are padded to
+    /// their full width, so names of different lengths never meet
+    fn hashed_name(hash: u64, digits: usize) -> String {
+        if digits == HASH_DIGITS {
+            format!("{:x}", hash & 0xFFFFFF)
+        } else if digits >= 16 {
+            format!("{:016x}", hash)
+        } else {
+            format!("{:0width$x}", hash & ((1 << (digits * 4)) - 1), width = digits)
+        }
+    }
+
+    /// The variable for a token that isn't in any scale, kept readable so it can be found
+    fn unknown(&self, name: &str) -> String {
+        self.prefixed(&name.replace('.', "-"))
+    }
+
+    fn prefixed(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("--{}", name)
+        } else {
+            format!("--{}-{}", self.prefix, name)
+        }
+    }
+}
 
 pub struct TokenResolver {
     theme: Theme,
     property_map: HashMap<String, String>,
+    variables: VariableNamer,
+    output: TokenOutput,
+    inline_depth: Cell<usize>,
 }
 
 impl TokenResolver {
-    pub fn new(theme: &Theme) -> Self {
+    pub fn new(theme: &Theme, variables: &VariableNamer) -> Self {
+        Self::with_output(theme, variables, TokenOutput::Var)
+    }
+
+    /// Creates a resolver that writes known tokens in the given `tokenOutput` form
+    pub fn with_output(theme: &Theme, variables: &VariableNamer, output: TokenOutput) -> Self {
         Self {
             theme: theme.clone(),
             property_map: Self::build_property_map(),
+            variables: variables.clone(),
+            output,
+            inline_depth: Cell::new(0),
         }
     }
 
+    /// The CSS variable name for a token, e.g. `--stoop-colors-primary`
+    pub fn variable_name(&self, scale: &str, key: &str) -> String {
+        self.variables.token(scale, key)
+    }
+
+    /// Resolves a token that makes up the whole value of `property`
     pub fn resolve(&self, token: &str, property: &str) -> String {
+        self.resolve_token(token, property, None)
+    }
+
+    /// Resolves a token that is one part of a larger value, like `$focus` in
+    /// `0 0 0 2px $focus`. Inside shorthands the token's slot (see `shorthand_slots`)
+    /// decides the scale.
+    pub fn resolve_part(&self, token: &str, property: &str, slot: Option<Slot>) -> String {
+        self.resolve_token(token, property, slot)
+    }
+
+    /// Scales an unprefixed token in a shorthand slot could belong to, when more than one
+    /// matches. The first wins; callers warn about the rest.
+    pub fn ambiguous_scales(&self, token: &str, slot: Slot) -> Option<Vec<&'static str>> {
+        let name = token.trim_start_matches('-').strip_prefix('$')?;
+
+        if name.contains('.') {
+            return None;
+        }
+
+        let scales = self.slot_scales(name, slot);
+        (scales.len() > 1).then_some(scales)
+    }
+
+    fn resolve_token(&self, token: &str, property: &str, slot: Option<Slot>) -> String {
+        if let Some(token_name) = token.strip_prefix("-$") {
+            return self.resolve_negated(token_name, property, slot, token);
+        }
+
         if !token.starts_with('$') {
             return token.to_string();
         }
@@ -25,69 +241,311 @@ impl TokenResolver {
             return self.resolve_explicit(token_name);
         }
 
-        self.resolve_shorthand(token_name, property)
+        self.resolve_shorthand(token_name, property, slot)
+    }
+
+    /// `-$md` negates space and sizes tokens as `calc(var(--stoop-space-md) * -1)`
+    /// Other scales have no meaningful negative, so the value is left as written
+    fn resolve_negated(&self, token: &str, property: &str, slot: Option<Slot>, original: &str) -> String {
+        match self.negated_scale(token, property, slot) {
+            Some(scale) if scale == "space" || scale == "sizes" => {
+                let reference = match token.split_once('.') {
+                    Some(_) => self.resolve_explicit(token),
+                    None => self.token_reference(&scale, token),
+                };
+                format!("calc({} * -1)", reference)
+            }
+            _ => original.to_string(),
+        }
+    }
+
+    /// The scale of a `-$token` that can't be negated because it is neither space nor
+    /// sizes, so callers can report the value being written as-is
+    pub fn unnegatable_scale(&self, token: &str, property: &str, slot: Option<Slot>) -> Option<String> {
+        let name = token.strip_prefix("-$")?;
+
+        self.negated_scale(name, property, slot)
+            .filter(|scale| scale != "space" && scale != "sizes")
+    }
+
+    /// The scale a token name resolves to for `property`, if it can be determined
+    fn token_scale(&self, token: &str, property: &str, slot: Option<Slot>) -> Option<String> {
+        self.contextual_scale(token, property, slot)
+            .or_else(|| self.find_token_in_theme(token))
+    }
+
+    /// Like `token_scale`, but a negated token that its property doesn't place (e.g.
+    /// `translateX(-$sm)`) is only looked up in scales holding numbers, not colors
+    fn negated_scale(&self, token: &str, property: &str, slot: Option<Slot>) -> Option<String> {
+        self.contextual_scale(token, property, slot).or_else(|| {
+            NUMERIC_SCALES
+                .iter()
+                .find(|scale| self.token_exists_in_scale(token, scale))
+                .map(|scale| scale.to_string())
+        })
+    }
+
+    /// The scale given by an explicit `scale.token` name, the token's shorthand slot or
+    /// the property's own scale
+    fn contextual_scale(&self, token: &str, property: &str, slot: Option<Slot>) -> Option<String> {
+        if let Some((scale, _)) = token.split_once('.') {
+            return Some(normalize_name(scale, Case::Kebab));
+        }
+
+        if let Some(slot) = slot {
+            if let Some(scale) = self.slot_scales(token, slot).first() {
+                return Some(scale.to_string());
+            }
+        }
+
+        self.property_map
+            .get(property)
+            .filter(|scale| self.token_exists_in_scale(token, scale))
+            .cloned()
+    }
+
+    /// Scales defining `token` among the slot's own, or else among every shorthand scale,
+    /// so a color given where a length could go still resolves
+    fn slot_scales(&self, token: &str, slot: Slot) -> Vec<&'static str> {
+        let defined = |scales: &[&'static str]| -> Vec<&'static str> {
+            scales
+                .iter()
+                .copied()
+                .filter(|scale| self.token_exists_in_scale(token, scale))
+                .collect()
+        };
+
+        let own = defined(slot.scales());
+        if own.is_empty() {
+            defined(SHORTHAND_SCALES)
+        } else {
+            own
+        }
     }
 
     fn resolve_explicit(&self, token: &str) -> String {
         let parts: Vec<&str> = token.split('.').collect();
         if parts.len() == 2 {
-            format!("var(--{}-{})", parts[0], parts[1])
+            self.token_reference(&normalize_name(parts[0], Case::Kebab), parts[1])
         } else {
-            format!("var(--{})", token.replace('.', "-"))
+            format!("var({})", self.variables.unknown(token))
         }
     }
 
-    fn resolve_shorthand(&self, token: &str, property: &str) -> String {
-        if let Some(scale) = self.property_map.get(property) {
-            if self.token_exists_in_scale(token, scale) {
-                return format!("var(--{}-{})", scale, token);
+    /// Checks a `$token`, `$scale.token` or `-$token` reference against the theme
+    /// Unknown tokens fail with the closest known reference to suggest, if any
+    pub fn check_token(&self, token: &str) -> Result<(), Option<String>> {
+        let Some(name) = token.trim_start_matches('-').strip_prefix('$') else {
+            return Ok(());
+        };
+        let scales = self.theme.scales();
+
+        match name.split_once('.') {
+            Some((scale, token_name)) => {
+                let scale_name = normalize_name(scale, Case::Kebab);
+
+                match scales.iter().find(|(name, _)| *name == scale_name) {
+                    Some((_, tokens)) if tokens.contains_key(token_name) => Ok(()),
+                    Some((_, tokens)) => Err(
+                        closest_match(token_name, tokens.keys().map(String::as_str))
+                            .map(|suggestion| format!("${}.{}", scale, suggestion)),
+                    ),
+                    None => Err(None),
+                }
             }
+            None if scales.iter().any(|(_, tokens)| tokens.contains_key(name)) => Ok(()),
+            None => Err(
+                closest_match(name, scales.iter().flat_map(|(_, tokens)| tokens.keys().map(String::as_str)))
+                    .map(|suggestion| format!("${}", suggestion)),
+            ),
         }
+    }
 
-        if let Some(scale) = self.find_token_in_theme(token) {
-            return format!("var(--{}-{})", scale, token);
+    /// Resolves token references inside a theme value, e.g. `$blue600` or
+    /// `0 0 0 2px $colors.primary`. Shorthand tokens prefer the value's own scale.
+    pub fn resolve_theme_value(&self, value: &str, scale: &str) -> String {
+        match parse_style_string(value.to_string()) {
+            StyleValue::Static(value) => value,
+            StyleValue::Token(token) => self.resolve_theme_token(&token, scale),
+            StyleValue::Compound(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    StylePart::Static(s) => s.clone(),
+                    StylePart::Token(token) => self.resolve_theme_token(token, scale),
+                })
+                .collect(),
         }
-
-        format!("var(--{})", token)
     }
 
-    fn token_exists_in_scale(&self, token: &str, scale: &str) -> bool {
-        match scale {
-            "colors" => self.theme.colors.as_ref()
-                .map(|m| m.contains_key(token))
-                .unwrap_or(false),
-            "space" => self.theme.space.as_ref()
-                .map(|m| m.contains_key(token))
-                .unwrap_or(false),
-            "fontSizes" | "font-sizes" => self.theme.font_sizes.as_ref()
-                .map(|m| m.contains_key(token))
-                .unwrap_or(false),
-            _ => false,
+    fn resolve_theme_token(&self, token: &str, scale: &str) -> String {
+        let Some((target_scale, key)) = self.theme_reference(token, scale) else {
+            return self.resolve(token, "");
+        };
+
+        let var = self.token_reference(&target_scale, &key);
+
+        if !token.starts_with('-') {
+            var
+        } else if target_scale == "space" || target_scale == "sizes" {
+            format!("calc({} * -1)", var)
+        } else {
+            token.to_string()
         }
     }
 
-    fn find_token_in_theme(&self, token: &str) -> Option<String> {
-        if let Some(colors) = &self.theme.colors {
-            if colors.contains_key(token) {
-                return Some("colors".to_string());
+    /// The (scale, token) a reference inside a theme value points at, if it exists
+    fn theme_reference(&self, token: &str, scale: &str) -> Option<(String, String)> {
+        let name = token.trim_start_matches('-').strip_prefix('$')?;
+
+        match name.split_once('.') {
+            Some((target_scale, key)) => {
+                let target_scale = normalize_name(target_scale, Case::Kebab);
+                self.token_exists_in_scale(key, &target_scale)
+                    .then(|| (target_scale, key.to_string()))
             }
+            None if self.token_exists_in_scale(name, scale) => Some((scale.to_string(), name.to_string())),
+            None => self.find_token_in_theme(name).map(|target_scale| (target_scale, name.to_string())),
         }
+    }
 
-        if let Some(space) = &self.theme.space {
-            if space.contains_key(token) {
-                return Some("space".to_string());
-            }
+    /// Finds theme tokens that reference each other in a loop, returned as
+    /// `scale.token` names that end where they started
+    pub fn find_reference_cycle(&self) -> Option<Vec<String>> {
+        let mut nodes: Vec<(String, String)> = self
+            .theme
+            .scales()
+            .into_iter()
+            .flat_map(|(scale, tokens)| tokens.keys().map(move |key| (scale.to_string(), key.clone())))
+            .collect();
+        nodes.sort();
+
+        let mut finished = HashSet::new();
+
+        nodes
+            .iter()
+            .find_map(|node| self.visit_reference(node, &mut Vec::new(), &mut finished))
+    }
+
+    fn visit_reference(
+        &self,
+        node: &(String, String),
+        path: &mut Vec<(String, String)>,
+        finished: &mut HashSet<(String, String)>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|visited| visited == node) {
+            return Some(
+                path[start..]
+                    .iter()
+                    .chain(std::iter::once(node))
+                    .map(|(scale, key)| format!("{}.{}", scale, key))
+                    .collect(),
+            );
         }
 
-        if let Some(font_sizes) = &self.theme.font_sizes {
-            if font_sizes.contains_key(token) {
-                return Some("font-sizes".to_string());
+        if finished.contains(node) {
+            return None;
+        }
+
+        path.push(node.clone());
+
+        for target in self.theme_references(node) {
+            if let Some(cycle) = self.visit_reference(&target, path, finished) {
+                return Some(cycle);
             }
         }
 
+        path.pop();
+        finished.insert(node.clone());
         None
     }
 
+    /// Tokens referenced by the value of a theme token
+    fn theme_references(&self, (scale, key): &(String, String)) -> Vec<(String, String)> {
+        let value = self
+            .theme
+            .scales()
+            .into_iter()
+            .find(|(name, _)| name == scale)
+            .and_then(|(_, tokens)| tokens.get(key).cloned());
+
+        let tokens = match value.map(parse_style_string) {
+            Some(StyleValue::Token(token)) => vec![token],
+            Some(StyleValue::Compound(parts)) => parts
+                .into_iter()
+                .filter_map(|part| match part {
+                    StylePart::Token(token) => Some(token),
+                    StylePart::Static(_) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        tokens
+            .iter()
+            .filter_map(|token| self.theme_reference(token, scale))
+            .collect()
+    }
+
+    fn resolve_shorthand(&self, token: &str, property: &str, slot: Option<Slot>) -> String {
+        match self.token_scale(token, property, slot) {
+            Some(scale) => self.token_reference(&scale, token),
+            None => format!("var({})", self.variables.unknown(token)),
+        }
+    }
+
+    /// A known token as CSS, in the configured `tokenOutput` form: `var(--stoop-colors-primary)`,
+    /// `var(--stoop-colors-primary, #0070f3)` or `#0070f3`
+    fn token_reference(&self, scale: &str, key: &str) -> String {
+        let var_name = self.variable_name(scale, key);
+
+        let value = match self.output {
+            TokenOutput::Var => None,
+            TokenOutput::VarWithFallback | TokenOutput::Inline => self.token_value(scale, key),
+        };
+
+        match (self.output, value) {
+            (TokenOutput::VarWithFallback, Some(value)) => format!("var({}, {})", var_name, value),
+            (TokenOutput::Inline, Some(value)) => value,
+            _ => format!("var({})", var_name),
+        }
+    }
+
+    /// A token's theme value with its own references resolved
+    fn token_value(&self, scale: &str, key: &str) -> Option<String> {
+        let depth = self.inline_depth.get();
+        if depth >= MAX_INLINE_DEPTH {
+            return None;
+        }
+
+        let raw = self
+            .theme
+            .scales()
+            .into_iter()
+            .find(|(name, _)| *name == scale)
+            .and_then(|(_, tokens)| tokens.get(key).cloned())?;
+
+        self.inline_depth.set(depth + 1);
+        let value = self.resolve_theme_value(&raw, scale);
+        self.inline_depth.set(depth);
+
+        Some(value)
+    }
+
+    fn token_exists_in_scale(&self, token: &str, scale: &str) -> bool {
+        self.theme
+            .scales()
+            .into_iter()
+            .any(|(name, tokens)| name == scale && tokens.contains_key(token))
+    }
+
+    fn find_token_in_theme(&self, token: &str) -> Option<String> {
+        self.theme
+            .scales()
+            .into_iter()
+            .find(|(_, tokens)| tokens.contains_key(token))
+            .map(|(name, _)| name.to_string())
+    }
+
     fn build_property_map() -> HashMap<String, String> {
         let mut map = HashMap::new();
 
@@ -116,6 +574,22 @@ impl TokenResolver {
         map.insert("rowGap".to_string(), "space".to_string());
         map.insert("columnGap".to_string(), "space".to_string());
 
+        // Position properties
+        map.insert("top".to_string(), "space".to_string());
+        map.insert("right".to_string(), "space".to_string());
+        map.insert("bottom".to_string(), "space".to_string());
+        map.insert("left".to_string(), "space".to_string());
+        map.insert("inset".to_string(), "space".to_string());
+
+        // Size properties
+        map.insert("width".to_string(), "sizes".to_string());
+        map.insert("height".to_string(), "sizes".to_string());
+        map.insert("minWidth".to_string(), "sizes".to_string());
+        map.insert("maxWidth".to_string(), "sizes".to_string());
+        map.insert("minHeight".to_string(), "sizes".to_string());
+        map.insert("maxHeight".to_string(), "sizes".to_string());
+        map.insert("flexBasis".to_string(), "sizes".to_string());
+
         // Font properties
         map.insert("fontSize".to_string(), "font-sizes".to_string());
         map.insert("fontWeight".to_string(), "font-weights".to_string());
@@ -139,3 +613,375 @@ impl TokenResolver {
         map
     }
 }
+
+/// Splits a string value into static text and `$token` references
+pub fn parse_style_string(value: String) -> StyleValue {
+    let is_single_token = (value.starts_with('$') || value.starts_with("-$"))
+        && !value.contains(|ch: char| ch.is_whitespace() || ch == ',' || ch == '(' || ch == ')');
+
+    if is_single_token {
+        StyleValue::Token(value)
+    } else if value.contains('$') {
+        parse_compound_value(&value)
+    } else {
+        StyleValue::Static(value)
+    }
+}
+
+fn parse_compound_value(value: &str) -> StyleValue {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+
+    // Improved token parsing that handles:
+    // - Tokens at start: "$md"
+    // - Tokens in middle: "calc($md + 10px)"
+    // - Multiple tokens: "$md $lg"
+    // - Tokens with dots: "$colors.primary"
+    // - Negated tokens: "translate(-$sm, -$sm)"
+
+    for ch in value.chars() {
+        if ch == '$' {
+            // A '-' directly before the token negates it, unless it belongs to a word
+            let negated = current.ends_with('-')
+                && current[..current.len() - 1]
+                    .chars()
+                    .last()
+                    .is_none_or(|prev| prev.is_whitespace() || prev == ',' || prev == '(');
+
+            if negated {
+                current.pop();
+            }
+
+            // Save current static part if any
+            if !current.is_empty() {
+                parts.push(StylePart::Static(current.clone()));
+                current.clear();
+            }
+            in_token = true;
+            if negated {
+                current.push('-');
+            }
+            current.push(ch);
+        } else if in_token {
+            // Continue token until we hit whitespace, comma, or closing paren
+            if ch.is_whitespace() || ch == ',' || ch == ')' {
+                if !current.is_empty() {
+                    parts.push(StylePart::Token(current.clone()));
+                    current.clear();
+                }
+                in_token = false;
+                current.push(ch);
+            } else {
+                current.push(ch);
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+
+    // Add remaining part
+    if !current.is_empty() {
+        if in_token {
+            parts.push(StylePart::Token(current));
+        } else {
+            parts.push(StylePart::Static(current));
+        }
+    }
+
+    StyleValue::Compound(parts)
+}
+
+/// The slot of each token in a shorthand value, in order, or `None` when `property`
+/// isn't a shorthand. Each comma-separated layer is split into words; the leading
+/// length-like words fill the length slots (one, or four for `boxShadow`) and every
+/// other word is in the color slot.
+pub fn shorthand_slots(parts: &[StylePart], property: &str) -> Option<Vec<Slot>> {
+    let max_lengths = match property {
+        "boxShadow" => 4,
+        _ if SHORTHAND_PROPERTIES.contains(&property) => 1,
+        _ => return None,
+    };
+
+    // Words of each layer, with the number of tokens in each
+    let mut layers: Vec<Vec<(String, usize)>> = vec![Vec::new()];
+    let mut word = (String::new(), 0);
+    let mut depth = 0usize;
+
+    for part in parts {
+        match part {
+            StylePart::Token(token) => {
+                word.0.push_str(token);
+                word.1 += 1;
+            }
+            StylePart::Static(text) => {
+                for ch in text.chars() {
+                    match ch {
+                        '(' => depth += 1,
+                        ')' => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+
+                    if depth == 0 && (ch.is_whitespace() || ch == ',') {
+                        if !word.0.is_empty() {
+                            layers.last_mut().unwrap().push(std::mem::take(&mut word));
+                        }
+                        if ch == ',' {
+                            layers.push(Vec::new());
+                        }
+                    } else {
+                        word.0.push(ch);
+                    }
+                }
+            }
+        }
+    }
+
+    if !word.0.is_empty() {
+        layers.last_mut().unwrap().push(word);
+    }
+
+    let mut slots = Vec::new();
+
+    for layer in layers {
+        let mut lengths = 0;
+        let mut leading = true;
+
+        for (word, tokens) in layer {
+            if word == "inset" {
+                continue;
+            }
+
+            let length_like = tokens > 0
+                || word.starts_with(|ch: char| ch.is_ascii_digit() || ch == '.' || ch == '-')
+                || word.starts_with("calc(");
+            leading &= length_like;
+
+            let slot = if leading && lengths < max_lengths {
+                lengths += 1;
+                Slot::Length
+            } else {
+                Slot::Color
+            };
+            slots.extend(std::iter::repeat_n(slot, tokens));
+        }
+    }
+
+    Some(slots)
+}
+
+/// Closest candidate by edit distance, if it is close enough to be a likely typo
+fn closest_match<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_ch) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_ch != *b_ch);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_token() {
+        let mut colors = HashMap::new();
+        colors.insert("primary".to_string(), "#0070f3".to_string());
+        let theme = Theme {
+            colors: Some(colors),
+            ..Theme::default()
+        };
+        let variables = VariableNamer::new(&StoopConfig::default());
+        let resolver = TokenResolver::new(&theme, &variables);
+
+        assert_eq!(resolver.check_token("$primary"), Ok(()));
+        assert_eq!(resolver.check_token("-$colors.primary"), Ok(()));
+        assert_eq!(resolver.check_token("$primray"), Err(Some("$primary".to_string())));
+        assert_eq!(resolver.check_token("$colors.primry"), Err(Some("$colors.primary".to_string())));
+        assert_eq!(resolver.check_token("$secondary"), Err(None));
+        assert_eq!(resolver.check_token("$space.primary"), Err(None));
+    }
+
+    #[test]
+    fn test_unnegatable_scale() {
+        let mut colors = HashMap::new();
+        colors.insert("primary".to_string(), "#0070f3".to_string());
+        let mut space = HashMap::new();
+        space.insert("sm".to_string(), "8px".to_string());
+        let theme = Theme {
+            colors: Some(colors),
+            space: Some(space),
+            ..Theme::default()
+        };
+        let variables = VariableNamer::new(&StoopConfig::default());
+        let resolver = TokenResolver::new(&theme, &variables);
+
+        assert_eq!(resolver.unnegatable_scale("-$sm", "marginTop", None), None);
+        assert_eq!(resolver.unnegatable_scale("$primary", "color", None), None);
+        assert_eq!(resolver.unnegatable_scale("-$primary", "color", None), Some("colors".to_string()));
+        assert_eq!(resolver.resolve("-$primary", "color"), "-$primary");
+    }
+
+    #[test]
+    fn test_negated_token_prefers_numeric_scales() {
+        let theme = Theme {
+            colors: Some(HashMap::from([("sm".to_string(), "#ff0000".to_string())])),
+            space: Some(HashMap::from([("sm".to_string(), "8px".to_string())])),
+            ..Theme::default()
+        };
+        let variables = VariableNamer::new(&StoopConfig::default());
+        let resolver = TokenResolver::new(&theme, &variables);
+
+        assert_eq!(resolver.resolve("-$sm", "transform"), "calc(var(--stoop-space-sm) * -1)");
+        assert_eq!(resolver.unnegatable_scale("-$sm", "transform", None), None);
+        assert_eq!(resolver.resolve("$sm", "color"), "var(--stoop-colors-sm)");
+    }
+
+    #[test]
+    fn test_theme_references() {
+        let mut colors = HashMap::new();
+        colors.insert("blue600".to_string(), "#2563eb".to_string());
+        colors.insert("primary".to_string(), "$blue600".to_string());
+        let mut shadows = HashMap::new();
+        shadows.insert("focus".to_string(), "0 0 0 2px $colors.primary".to_string());
+        let mut theme = Theme {
+            colors: Some(colors),
+            shadows: Some(shadows),
+            ..Theme::default()
+        };
+        let variables = VariableNamer::new(&StoopConfig::default());
+
+        let resolver = TokenResolver::new(&theme, &variables);
+        assert_eq!(resolver.resolve_theme_value("$blue600", "colors"), "var(--stoop-colors-blue600)");
+        assert_eq!(
+            resolver.resolve_theme_value("0 0 0 2px $colors.primary", "shadows"),
+            "0 0 0 2px var(--stoop-colors-primary)"
+        );
+        assert_eq!(resolver.find_reference_cycle(), None);
+
+        theme.colors.as_mut().unwrap().insert("blue600".to_string(), "$primary".to_string());
+        let resolver = TokenResolver::new(&theme, &variables);
+        assert_eq!(
+            resolver.find_reference_cycle(),
+            Some(vec!["colors.blue600".to_string(), "colors.primary".to_string(), "colors.blue600".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_token_output() {
+        let mut colors = HashMap::new();
+        colors.insert("blue600".to_string(), "#2563eb".to_string());
+        colors.insert("primary".to_string(), "$blue600".to_string());
+        let theme = Theme {
+            colors: Some(colors),
+            ..Theme::default()
+        };
+        let variables = VariableNamer::new(&StoopConfig::default());
+
+        let fallback = TokenResolver::with_output(&theme, &variables, TokenOutput::VarWithFallback);
+        assert_eq!(
+            fallback.resolve("$primary", "color"),
+            "var(--stoop-colors-primary, var(--stoop-colors-blue600, #2563eb))"
+        );
+
+        let inline = TokenResolver::with_output(&theme, &variables, TokenOutput::Inline);
+        assert_eq!(inline.resolve("$colors.primary", "color"), "#2563eb");
+        assert_eq!(inline.resolve("$missing", "color"), "var(--stoop-missing)");
+    }
+
+    #[test]
+    fn test_variable_names() {
+        let mut config = StoopConfig::default();
+        assert_eq!(VariableNamer::new(&config).token("font-sizes", "lg"), "--stoop-font-sizes-lg");
+
+        config.variable_names = VariableNaming::Hashed;
+        let hashed = VariableNamer::new(&config);
+        assert!(hashed.token("colors", "primary").starts_with("--stoop-"));
+        assert_ne!(hashed.token("colors", "primary"), hashed.token("colors", "secondary"));
+        assert_eq!(hashed.token("colors", "primary"), hashed.token("colors", "primary"));
+
+        // Enough tokens that some six-digit names collide
+        config.theme.colors = Some((0..10_000).map(|i| (format!("c{}", i), "red".to_string())).collect());
+        let hashed = VariableNamer::new(&config);
+        let names: HashSet<String> = (0..10_000).map(|i| hashed.token("colors", &format!("c{}", i))).collect();
+        assert_eq!(names.len(), 10_000);
+        assert!(names.iter().any(|name| name.len() > "--stoop-".len() + 6));
+    }
+
+    #[test]
+    fn test_shorthand_slots() {
+        let slots = |value: &str, property: &str| match parse_style_string(value.to_string()) {
+            StyleValue::Compound(parts) => shorthand_slots(&parts, property),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(slots("1px solid $primary", "border"), Some(vec![Slot::Color]));
+        assert_eq!(slots("$sm solid $primary", "borderTop"), Some(vec![Slot::Length, Slot::Color]));
+        assert_eq!(
+            slots("0 0 0 $sm $focus", "boxShadow"),
+            Some(vec![Slot::Length, Slot::Color])
+        );
+        assert_eq!(
+            slots("inset 0 -$sm $md rgba(0, 0, 0, 0.1), 0 $sm $lg 0 $shadow", "boxShadow"),
+            Some(vec![Slot::Length, Slot::Length, Slot::Length, Slot::Length, Slot::Color])
+        );
+        assert_eq!(slots("calc($sm + 1px) solid $primary", "outline"), Some(vec![Slot::Length, Slot::Color]));
+        assert_eq!(slots("$sm $md", "margin"), None);
+    }
+
+    #[test]
+    fn test_resolve_shorthand_slots() {
+        let mut colors = HashMap::new();
+        colors.insert("sm".to_string(), "#eee".to_string());
+        colors.insert("focus".to_string(), "#09f".to_string());
+        let mut space = HashMap::new();
+        space.insert("sm".to_string(), "8px".to_string());
+        space.insert("md".to_string(), "16px".to_string());
+        let mut sizes = HashMap::new();
+        sizes.insert("md".to_string(), "20rem".to_string());
+        let theme = Theme {
+            colors: Some(colors),
+            space: Some(space),
+            sizes: Some(sizes),
+            ..Theme::default()
+        };
+        let variables = VariableNamer::new(&StoopConfig::default());
+        let resolver = TokenResolver::new(&theme, &variables);
+
+        // `sm` is both a color and a space token: the slot picks the scale, without a tie
+        assert_eq!(resolver.resolve_part("$sm", "boxShadow", Some(Slot::Length)), "var(--stoop-space-sm)");
+        assert_eq!(resolver.resolve_part("$sm", "boxShadow", Some(Slot::Color)), "var(--stoop-colors-sm)");
+        assert_eq!(resolver.ambiguous_scales("$sm", Slot::Length), None);
+        assert_eq!(resolver.ambiguous_scales("$sm", Slot::Color), None);
+
+        // A color where a length could go still resolves as a color
+        assert_eq!(resolver.resolve_part("$focus", "boxShadow", Some(Slot::Length)), "var(--stoop-colors-focus)");
+
+        // `md` is in both length scales, a tie within the slot
+        assert_eq!(resolver.resolve_part("$md", "border", Some(Slot::Length)), "var(--stoop-space-md)");
+        assert_eq!(resolver.ambiguous_scales("$md", Slot::Length), Some(vec!["space", "sizes"]));
+        assert_eq!(resolver.ambiguous_scales("$md", Slot::Color), Some(vec!["space", "sizes"]));
+        assert_eq!(resolver.ambiguous_scales("$space.md", Slot::Length), None);
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+
 use swc_core::ecma::ast::*;
-use swc_core::common::{DUMMY_SP, SyntaxContext};
+use swc_core::common::{Spanned, DUMMY_SP, SyntaxContext};
 use swc_core::ecma::utils::quote_ident;
 
 use crate::config::StoopConfig;
+use crate::error::{emit_warning, StoopError};
 use crate::types::{StyleExtraction, CSSOutput};
 
 pub struct ComponentTransformer {
@@ -60,7 +63,7 @@ impl ComponentTransformer {
                 callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
                     span: DUMMY_SP,
                     obj: Box::new(Expr::Ident(quote_ident!("Object").into())),
-                    prop: MemberProp::Ident(quote_ident!("assign").into()),
+                    prop: MemberProp::Ident(quote_ident!("assign")),
                 }))),
                 args: vec![
                     ExprOrSpread {
@@ -72,7 +75,7 @@ impl ComponentTransformer {
                         expr: Box::new(Expr::Object(ObjectLit {
                             span: DUMMY_SP,
                             props: vec![PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
-                                key: PropName::Ident(quote_ident!("selector").into()),
+                                key: PropName::Ident(quote_ident!("selector")),
                                 value: Box::new(selector_obj),
                             })))],
                         })),
@@ -107,7 +110,7 @@ impl ComponentTransformer {
             span: DUMMY_SP,
             ctxt: SyntaxContext::empty(),
             params,
-            body: Box::new(BlockStmtOrExpr::BlockStmt(body)),
+            body: Box::new(ArrowFunctionBody::FunctionBody(body)),
             is_async: false,
             is_generator: false,
             type_params: None,
@@ -119,16 +122,15 @@ impl ComponentTransformer {
         &self,
         extraction: &StyleExtraction,
         css_output: &CSSOutput,
-    ) -> BlockStmt {
-        let mut stmts = vec![];
-
-        stmts.push(self.create_destructuring(extraction));
-        stmts.push(self.create_classname_stmt(css_output, extraction));
-        stmts.push(self.create_return_stmt(extraction));
+    ) -> FunctionBody {
+        let stmts = vec![
+            self.create_destructuring(extraction),
+            self.create_classname_stmt(css_output, extraction),
+            self.create_return_stmt(extraction),
+        ];
 
-        BlockStmt {
+        FunctionBody {
             span: DUMMY_SP,
-            ctxt: SyntaxContext::empty(),
             stmts,
         }
     }
@@ -144,16 +146,7 @@ impl ComponentTransformer {
         }));
 
         // Extract variant props
-        for variant_name in extraction.variants.keys() {
-            props.push(ObjectPatProp::Assign(AssignPatProp {
-                span: DUMMY_SP,
-                key: BindingIdent {
-                    id: Ident::new(variant_name.clone().into(), DUMMY_SP, SyntaxContext::empty()),
-                    type_ann: None,
-                },
-                value: None,
-            }));
-        }
+        props.extend(self.create_variant_pattern_props(extraction));
 
         props.push(ObjectPatProp::Assign(AssignPatProp {
             span: DUMMY_SP,
@@ -190,18 +183,73 @@ impl ComponentTransformer {
         })))
     }
 
+    /// Binds each variant prop, with `defaultVariants` as destructuring defaults (`size = "md"`)
+    fn create_variant_pattern_props(&self, extraction: &StyleExtraction) -> Vec<ObjectPatProp> {
+        extraction
+            .variants
+            .keys()
+            .map(|variant_name| {
+                ObjectPatProp::Assign(AssignPatProp {
+                    span: DUMMY_SP,
+                    key: BindingIdent {
+                        id: Ident::new(variant_name.clone().into(), DUMMY_SP, SyntaxContext::empty()),
+                        type_ann: None,
+                    },
+                    value: extraction
+                        .default_variants
+                        .get(variant_name)
+                        .map(|value_name| Box::new(self.variant_value_literal(value_name))),
+                })
+            })
+            .collect()
+    }
+
     fn create_classname_stmt(
         &self,
         css_output: &CSSOutput,
-        _extraction: &StyleExtraction,
+        extraction: &StyleExtraction,
     ) -> Stmt {
-        // Simplified - no CSS prop handling
+        // `css` props are compiled into `className` at the JSX call site
+        let mut args = self.create_classname_args(css_output, extraction);
+
+        args.push(ExprOrSpread {
+            spread: None,
+            expr: Box::new(Expr::Ident(quote_ident!("className").into())),
+        });
+
+        let clsx_call = self.create_clsx_call(args);
+
+        Stmt::Decl(Decl::Var(Box::new(VarDecl {
+            span: DUMMY_SP,
+            ctxt: SyntaxContext::empty(),
+            kind: VarDeclKind::Const,
+            declare: false,
+            decls: vec![VarDeclarator {
+                span: DUMMY_SP,
+                name: Pat::Ident(BindingIdent {
+                    id: quote_ident!("finalClassName").into(),
+                    type_ann: None,
+                }),
+                init: Some(Box::new(clsx_call)),
+                definite: false,
+            }],
+        })))
+    }
+
+    /// Base class followed by one conditional per variant value
+    fn create_classname_args(
+        &self,
+        css_output: &CSSOutput,
+        _extraction: &StyleExtraction,
+    ) -> Vec<ExprOrSpread> {
         let mut args = vec![];
 
         if !css_output.base_class.is_empty() {
             args.push(self.string_literal(&css_output.base_class));
         }
 
+        let has_breakpoints = !self.config.breakpoint_names().is_empty();
+
         for (variant_name, variant_classes) in &css_output.variant_classes {
             for (value_name, class_name) in variant_classes {
                 args.push(self.create_variant_conditional(
@@ -210,36 +258,147 @@ impl ComponentTransformer {
                     class_name,
                 ));
             }
+
+            if has_breakpoints {
+                args.push(self.create_responsive_variant_call(variant_name, variant_classes));
+            }
         }
 
-        args.push(ExprOrSpread {
+        // size === "lg" && color === "danger" && "compound-class"
+        // A responsive object never equals a value, so compounds only match plain props
+        for (conditions, class_name) in &css_output.compound_variant_classes {
+            let condition = conditions
+                .iter()
+                .map(|(variant_name, value_name)| self.create_variant_comparison(variant_name, value_name))
+                .reduce(|left, right| {
+                    Expr::Bin(BinExpr {
+                        span: DUMMY_SP,
+                        op: BinaryOp::LogicalAnd,
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    })
+                });
+
+            if let Some(condition) = condition {
+                args.push(ExprOrSpread {
+                    spread: None,
+                    expr: Box::new(Expr::Bin(BinExpr {
+                        span: DUMMY_SP,
+                        op: BinaryOp::LogicalAnd,
+                        left: Box::new(condition),
+                        right: self.string_literal(class_name).expr,
+                    })),
+                });
+            }
+        }
+
+        args
+    }
+
+    /// Creates `responsiveVariant(size, { sm: "...", lg: "..." })`, which maps object props
+    /// such as `{ '@initial': 'sm', '@bp2': 'lg' }` to breakpoint classes at runtime
+    fn create_responsive_variant_call(
+        &self,
+        variant_name: &str,
+        variant_classes: &HashMap<String, String>,
+    ) -> ExprOrSpread {
+        let mut values: Vec<_> = variant_classes.iter().collect();
+        values.sort();
+
+        let props = values
+            .into_iter()
+            .map(|(value_name, class_name)| {
+                PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                    key: PropName::Str(Str {
+                        span: DUMMY_SP,
+                        value: value_name.as_str().into(),
+                        raw: None,
+                    }),
+                    value: self.string_literal(class_name).expr,
+                })))
+            })
+            .collect();
+
+        ExprOrSpread {
             spread: None,
-            expr: Box::new(Expr::Ident(quote_ident!("className").into())),
-        });
+            expr: Box::new(Expr::Call(CallExpr {
+                span: DUMMY_SP,
+                ctxt: SyntaxContext::empty(),
+                callee: Callee::Expr(Box::new(Expr::Ident(quote_ident!("responsiveVariant").into()))),
+                args: vec![
+                    ExprOrSpread {
+                        spread: None,
+                        expr: Box::new(Expr::Ident(Ident::new(variant_name.into(), DUMMY_SP, SyntaxContext::empty()))),
+                    },
+                    ExprOrSpread {
+                        spread: None,
+                        expr: Box::new(Expr::Object(ObjectLit { span: DUMMY_SP, props })),
+                    },
+                ],
+                type_args: None,
+            })),
+        }
+    }
 
-        let clsx_call = Expr::Call(CallExpr {
+    fn create_clsx_call(&self, args: Vec<ExprOrSpread>) -> Expr {
+        Expr::Call(CallExpr {
             span: DUMMY_SP,
             ctxt: SyntaxContext::empty(),
             callee: Callee::Expr(Box::new(Expr::Ident(quote_ident!("clsx").into()))),
             args,
             type_args: None,
+        })
+    }
+
+    /// Creates the replacement for a standalone `css()` call: the class name string,
+    /// or `({ size, className } = {}) => clsx(...)` when variants are defined
+    pub fn create_class_selector(
+        &self,
+        extraction: &StyleExtraction,
+        css_output: &CSSOutput,
+    ) -> Expr {
+        if css_output.variant_classes.is_empty() {
+            return *self.string_literal(&css_output.base_class).expr;
+        }
+
+        let mut props = self.create_variant_pattern_props(extraction);
+
+        props.push(ObjectPatProp::Assign(AssignPatProp {
+            span: DUMMY_SP,
+            key: quote_ident!("className").into(),
+            value: None,
+        }));
+
+        let param = Pat::Assign(AssignPat {
+            span: DUMMY_SP,
+            left: Box::new(Pat::Object(ObjectPat {
+                span: DUMMY_SP,
+                props,
+                optional: false,
+                type_ann: None,
+            })),
+            right: Box::new(Expr::Object(ObjectLit {
+                span: DUMMY_SP,
+                props: vec![],
+            })),
         });
 
-        Stmt::Decl(Decl::Var(Box::new(VarDecl {
+        let mut args = self.create_classname_args(css_output, extraction);
+        args.push(ExprOrSpread {
+            spread: None,
+            expr: Box::new(Expr::Ident(quote_ident!("className").into())),
+        });
+
+        Expr::Arrow(ArrowExpr {
             span: DUMMY_SP,
             ctxt: SyntaxContext::empty(),
-            kind: VarDeclKind::Const,
-            declare: false,
-            decls: vec![VarDeclarator {
-                span: DUMMY_SP,
-                name: Pat::Ident(BindingIdent {
-                    id: quote_ident!("finalClassName").into(),
-                    type_ann: None,
-                }),
-                init: Some(Box::new(clsx_call)),
-                definite: false,
-            }],
-        })))
+            params: vec![param],
+            body: Box::new(ArrowFunctionBody::Expr(Box::new(self.create_clsx_call(args)))),
+            is_async: false,
+            is_generator: false,
+            type_params: None,
+            return_type: None,
+        })
     }
 
     fn create_variant_conditional(
@@ -248,28 +407,7 @@ impl ComponentTransformer {
         value_name: &str,
         class_name: &str,
     ) -> ExprOrSpread {
-        let variant_ident = Expr::Ident(Ident::new(variant_name.into(), DUMMY_SP, SyntaxContext::empty()));
-
-        // Handle boolean variants: "true" or "false" strings
-        let right_expr = if value_name == "true" {
-            Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: true }))
-        } else if value_name == "false" {
-            Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: false }))
-        } else {
-            // String comparison (default)
-            Expr::Lit(Lit::Str(Str {
-                span: DUMMY_SP,
-                value: value_name.into(),
-                raw: None,
-            }))
-        };
-
-        let comparison = Expr::Bin(BinExpr {
-            span: DUMMY_SP,
-            op: BinaryOp::EqEqEq,
-            left: Box::new(variant_ident),
-            right: Box::new(right_expr),
-        });
+        let comparison = self.create_variant_comparison(variant_name, value_name);
 
         ExprOrSpread {
             spread: None,
@@ -286,6 +424,34 @@ impl ComponentTransformer {
         }
     }
 
+    /// Creates `variant === value`, comparing against booleans for `true`/`false` keys
+    fn create_variant_comparison(&self, variant_name: &str, value_name: &str) -> Expr {
+        let variant_ident = Expr::Ident(Ident::new(variant_name.into(), DUMMY_SP, SyntaxContext::empty()));
+
+        Expr::Bin(BinExpr {
+            span: DUMMY_SP,
+            op: BinaryOp::EqEqEq,
+            left: Box::new(variant_ident),
+            right: Box::new(self.variant_value_literal(value_name)),
+        })
+    }
+
+    fn variant_value_literal(&self, value_name: &str) -> Expr {
+        // Handle boolean variants: "true" or "false" strings
+        if value_name == "true" {
+            Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: true }))
+        } else if value_name == "false" {
+            Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: false }))
+        } else {
+            // String comparison (default)
+            Expr::Lit(Lit::Str(Str {
+                span: DUMMY_SP,
+                value: value_name.into(),
+                raw: None,
+            }))
+        }
+    }
+
     fn create_return_stmt(&self, extraction: &StyleExtraction) -> Stmt {
         // Determine element: use 'as' prop if provided, otherwise use extracted element
         // If element is not a string (component composition), use it directly
@@ -318,11 +484,11 @@ impl ComponentTransformer {
 
         let props = vec![
             PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
-                key: PropName::Ident(quote_ident!("ref").into()),
+                key: PropName::Ident(quote_ident!("ref")),
                 value: Box::new(Expr::Ident(quote_ident!("ref").into())),
             }))),
             PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
-                key: PropName::Ident(quote_ident!("className").into()),
+                key: PropName::Ident(quote_ident!("className")),
                 value: Box::new(Expr::Ident(quote_ident!("finalClassName").into())),
             }))),
             PropOrSpread::Spread(SpreadElement {
@@ -358,6 +524,193 @@ impl ComponentTransformer {
         })
     }
 
+    /// Merges a generated class into a JSX element's `className` attribute. Returns true
+    /// when the merge wraps an expression in `clsx()`, which then needs importing.
+    ///
+    /// Spreads after the last `className` can override it, so their `className` is read
+    /// and merged too: `<div {...rest} />` gets `className={clsx(rest.className, "...")}`.
+    pub fn merge_jsx_class_name(&self, attrs: &mut Vec<JSXAttrOrSpread>, class_name: &str) -> bool {
+        let string_value = |value: String| {
+            JSXAttrValue::Str(Str {
+                span: DUMMY_SP,
+                value: value.into(),
+                raw: None,
+            })
+        };
+
+        let class_name_index = attrs.iter().rposition(|attr| match attr {
+            JSXAttrOrSpread::JSXAttr(attr) => {
+                matches!(&attr.name, JSXAttrName::Ident(name) if name.sym == "className")
+            }
+            _ => false,
+        });
+
+        let trailing_spreads: Vec<&Expr> = attrs[class_name_index.map_or(0, |index| index + 1)..]
+            .iter()
+            .filter_map(|attr| match attr {
+                JSXAttrOrSpread::SpreadElement(spread) => Some(&*spread.expr),
+                _ => None,
+            })
+            .collect();
+
+        if !trailing_spreads.is_empty() {
+            if let Some(unreadable) = trailing_spreads
+                .iter()
+                .find(|expr| !matches!(expr, Expr::Ident(_) | Expr::Member(_)))
+            {
+                emit_warning(
+                    unreadable.span(),
+                    &StoopError::InvalidStyle(
+                        "a `className` passed through this spread replaces the `css` prop class. Spread a variable instead of an expression so the two can be merged.".to_string(),
+                    ),
+                );
+            } else {
+                return self.merge_class_name_after_spreads(attrs, class_name_index, class_name);
+            }
+        }
+
+        let Some(index) = class_name_index else {
+            attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
+                span: DUMMY_SP,
+                name: JSXAttrName::Ident(quote_ident!("className")),
+                value: Some(string_value(class_name.to_string())),
+            }));
+            return false;
+        };
+
+        let JSXAttrOrSpread::JSXAttr(attr) = &mut attrs[index] else {
+            unreachable!("className index points at an attribute");
+        };
+
+        match attr.value.take() {
+            Some(JSXAttrValue::Str(s)) => {
+                let existing_class = String::from_utf8_lossy(s.value.as_bytes()).into_owned();
+                attr.value = Some(string_value(format!("{} {}", existing_class, class_name)));
+                false
+            }
+            Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                span,
+                expr: JSXExpr::Expr(expr),
+            })) => {
+                let clsx_call = self.create_clsx_call(vec![
+                    ExprOrSpread { spread: None, expr },
+                    self.string_literal(class_name),
+                ]);
+                attr.value = Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                    span,
+                    expr: JSXExpr::Expr(Box::new(clsx_call)),
+                }));
+                true
+            }
+            _ => {
+                attr.value = Some(string_value(class_name.to_string()));
+                false
+            }
+        }
+    }
+
+    /// Replaces the `className` at `class_name_index` (if any) with one after every spread:
+    /// `className={clsx(b.className ?? a.className ?? explicit, "...")}`, keeping JSX's
+    /// last-one-wins order while always adding the generated class
+    fn merge_class_name_after_spreads(
+        &self,
+        attrs: &mut Vec<JSXAttrOrSpread>,
+        class_name_index: Option<usize>,
+        class_name: &str,
+    ) -> bool {
+        let explicit = class_name_index.and_then(|index| match attrs.remove(index) {
+            JSXAttrOrSpread::JSXAttr(JSXAttr { value: Some(JSXAttrValue::Str(s)), .. }) => {
+                Some(Box::new(Expr::Lit(Lit::Str(s))))
+            }
+            JSXAttrOrSpread::JSXAttr(JSXAttr {
+                value: Some(JSXAttrValue::JSXExprContainer(JSXExprContainer { expr: JSXExpr::Expr(expr), .. })),
+                ..
+            }) => Some(expr),
+            _ => None,
+        });
+
+        let existing = attrs[class_name_index.unwrap_or(0)..]
+            .iter()
+            .filter_map(|attr| match attr {
+                JSXAttrOrSpread::SpreadElement(spread) => Some(spread.expr.clone()),
+                _ => None,
+            })
+            .fold(explicit, |previous, spread| {
+                let spread_class = Box::new(Expr::Member(MemberExpr {
+                    span: DUMMY_SP,
+                    obj: spread,
+                    prop: MemberProp::Ident(quote_ident!("className")),
+                }));
+
+                Some(match previous {
+                    Some(previous) => Box::new(Expr::Bin(BinExpr {
+                        span: DUMMY_SP,
+                        op: BinaryOp::NullishCoalescing,
+                        left: spread_class,
+                        right: previous,
+                    })),
+                    None => spread_class,
+                })
+            });
+
+        let mut args: Vec<ExprOrSpread> = existing
+            .map(|expr| ExprOrSpread { spread: None, expr })
+            .into_iter()
+            .collect();
+        args.push(self.string_literal(class_name));
+
+        attrs.push(JSXAttrOrSpread::JSXAttr(JSXAttr {
+            span: DUMMY_SP,
+            name: JSXAttrName::Ident(quote_ident!("className")),
+            value: Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                span: DUMMY_SP,
+                expr: JSXExpr::Expr(Box::new(self.create_clsx_call(args))),
+            })),
+        }));
+        true
+    }
+
+    /// Creates `{ className: "...", selector: ".../[data-theme]" }`, which replaces `createTheme()` calls
+    pub fn create_theme_object(&self, class_name: &str) -> Expr {
+        let prop = |key: &str, value: String| {
+            PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(IdentName::new(key.into(), DUMMY_SP)),
+                value: Box::new(Expr::Lit(Lit::Str(Str {
+                    span: DUMMY_SP,
+                    value: value.into(),
+                    raw: None,
+                }))),
+            })))
+        };
+
+        Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props: vec![
+                prop("className", class_name.to_string()),
+                prop("selector", format!(".{}", class_name)),
+            ],
+        })
+    }
+
+    /// Creates `() => {}`, which replaces compiled calls such as `globalCss()`
+    pub fn create_noop_function(&self) -> Expr {
+        let body = FunctionBody {
+            span: DUMMY_SP,
+            stmts: vec![],
+        };
+
+        Expr::Arrow(ArrowExpr {
+            span: DUMMY_SP,
+            ctxt: SyntaxContext::empty(),
+            params: vec![],
+            body: Box::new(ArrowFunctionBody::FunctionBody(body)),
+            is_async: false,
+            is_generator: false,
+            type_params: None,
+            return_type: None,
+        })
+    }
+
     fn string_literal(&self, value: &str) -> ExprOrSpread {
         ExprOrSpread {
             spread: None,
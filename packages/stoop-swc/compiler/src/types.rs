@@ -1,15 +1,46 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct StyleExtraction {
     pub component_name: String,
     pub element: String,
     pub base_styles: HashMap<String, StyleValue>,
-    pub variants: HashMap<String, HashMap<String, HashMap<String, StyleValue>>>,
+    pub variants: HashMap<String, HashMap<String, StyleBlock>>,
     pub nested_selectors: HashMap<String, HashMap<String, StyleValue>>,
+    pub compound_variants: Vec<CompoundVariant>,
+    /// Variant name -> value name used when the prop is omitted
+    pub default_variants: HashMap<String, String>,
     pub composed_component_class: Option<String>, // Class name from composed component
 }
 
+/// Styles applied when several variant values match at once, in source order
+#[derive(Debug, Clone)]
+pub struct CompoundVariant {
+    /// (variant name, value name) pairs that must all match
+    pub conditions: Vec<(String, String)>,
+    pub styles: StyleBlock,
+}
+
+/// Declarations for one selector plus its nested selectors and at-rules
+#[derive(Debug, Clone, Default)]
+pub struct StyleBlock {
+    pub styles: HashMap<String, StyleValue>,
+    pub nested_selectors: HashMap<String, HashMap<String, StyleValue>>,
+}
+
+/// A top-level rule from a `globalCss()` call, kept in source order
+#[derive(Debug, Clone)]
+pub enum GlobalRule {
+    /// `'html, body': { ... }`
+    Selector { selector: String, block: StyleBlock },
+    /// Declaration at-rules such as `'@font-face': [{ ... }, { ... }]`
+    AtRule { name: String, blocks: Vec<HashMap<String, StyleValue>> },
+    /// Grouping at-rules such as `'@media (...)': { body: { ... } }` or `'@bp1': { ... }`
+    Group { query: String, rules: Vec<GlobalRule> },
+}
+
 #[derive(Debug, Clone)]
 pub enum StyleValue {
     Static(String),
@@ -27,5 +58,75 @@ pub struct CSSOutput {
     pub base_class: String,
     pub selector_class: String, // Selector class name for component targeting
     pub variant_classes: HashMap<String, HashMap<String, String>>,
+    pub compound_variant_classes: Vec<(Vec<(String, String)>, String)>,
     pub css: String,
 }
+
+/// Build metadata written next to the stylesheet as `manifest.json`. Each compiled file
+/// merges its entries into the existing manifest rather than replacing it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    /// Source file -> component name -> classes
+    pub components: BTreeMap<String, BTreeMap<String, ComponentManifest>>,
+    pub themes: BTreeMap<String, ThemeCoverage>,
+    /// `scale.token` -> variable name, only written with hashed `variableNames`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    /// Scale -> token names across every compiled file, including `createTheme()` tokens,
+    /// which `tokens.js` and `tokens.d.ts` are generated from
+    pub tokens: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// A styled component's entry in the generated `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentManifest {
+    pub base_class: String,
+    pub variants: BTreeMap<String, BTreeMap<String, String>>,
+    pub default_variants: BTreeMap<String, String>,
+}
+
+/// A styled component's variant props, written to the `.d.ts` sidecar of its file
+#[derive(Debug, Clone)]
+pub struct ComponentDeclaration {
+    pub name: String,
+    pub element: String,
+    /// Variant name -> value names, sorted
+    pub variants: BTreeMap<String, Vec<String>>,
+    pub default_variants: BTreeMap<String, String>,
+}
+
+/// One source file's share of the stylesheet. Sections are kept per file in
+/// `styles.json` next to the stylesheet, so compiling a file replaces only its own rules.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StyleSections {
+    /// `createTheme()` variable blocks
+    pub scoped_themes: String,
+    /// `globalCss()` rules
+    pub global: String,
+    /// Styled component and `css()` rules
+    pub components: String,
+    /// Rules from JSX `css` props, written last so they override component rules
+    pub css_props: String,
+}
+
+impl StyleSections {
+    pub fn is_empty(&self) -> bool {
+        self.scoped_themes.is_empty()
+            && self.global.is_empty()
+            && self.components.is_empty()
+            && self.css_props.is_empty()
+    }
+}
+
+/// How an alternate theme lines up with the base theme, as `scale.token` names
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThemeCoverage {
+    /// Tokens the alternate theme defines but the base theme does not
+    pub added: Vec<String>,
+    /// Base tokens the alternate theme leaves at their base value
+    pub not_overridden: Vec<String>,
+}
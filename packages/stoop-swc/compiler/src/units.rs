@@ -0,0 +1,114 @@
+use crate::config::NumericUnitConfig;
+use crate::hasher::{normalize_name, Case, VENDOR_PREFIXES};
+
+/// Properties that take plain numbers, matching React's list. Numbers given to any
+/// other property get the configured unit.
+const UNITLESS_PROPERTIES: &[&str] = &[
+    "animationIterationCount",
+    "aspectRatio",
+    "borderImageOutset",
+    "borderImageSlice",
+    "borderImageWidth",
+    "boxFlex",
+    "boxFlexGroup",
+    "boxOrdinalGroup",
+    "columnCount",
+    "columns",
+    "flex",
+    "flexGrow",
+    "flexPositive",
+    "flexShrink",
+    "flexNegative",
+    "flexOrder",
+    "fontWeight",
+    "gridArea",
+    "gridRow",
+    "gridRowEnd",
+    "gridRowSpan",
+    "gridRowStart",
+    "gridColumn",
+    "gridColumnEnd",
+    "gridColumnSpan",
+    "gridColumnStart",
+    "lineClamp",
+    "lineHeight",
+    "opacity",
+    "order",
+    "orphans",
+    "scale",
+    "tabSize",
+    "widows",
+    "zIndex",
+    "zoom",
+    // SVG
+    "fillOpacity",
+    "floodOpacity",
+    "stopOpacity",
+    "strokeDasharray",
+    "strokeDashoffset",
+    "strokeMiterlimit",
+    "strokeOpacity",
+    "strokeWidth",
+];
+
+/// CSS for a number given to `property`: `padding: 16` -> `16px` (or `1rem` with
+/// `numericUnit: { unit: "rem", base: 16 }`), while `opacity: 0.5` and `0` stay as-is
+pub fn numeric_value(property: &str, value: f64, config: &NumericUnitConfig) -> String {
+    if value == 0.0 || is_unitless(property) {
+        return value.to_string();
+    }
+
+    match config.base {
+        Some(base) if base != 0.0 => format!("{}{}", value / base, config.unit),
+        _ => format!("{}{}", value, config.unit),
+    }
+}
+
+/// Custom properties are unitless too, since their use is unknown
+fn is_unitless(property: &str) -> bool {
+    if property.starts_with("--") {
+        return true;
+    }
+
+    // Kebab-case keys (`line-height`, `-webkit-line-clamp`) are checked in camelCase
+    let property = normalize_name(property, Case::Camel);
+
+    // `WebkitLineClamp`, `msFlexOrder` -> `lineClamp`, `flexOrder`
+    let unprefixed = VENDOR_PREFIXES
+        .iter()
+        .find_map(|(prefix, _)| {
+            property
+                .strip_prefix(prefix)
+                .filter(|rest| rest.starts_with(|ch: char| ch.is_ascii_uppercase()))
+        })
+        .map(|rest| format!("{}{}", rest[..1].to_lowercase(), &rest[1..]));
+
+    let name = unprefixed.as_deref().unwrap_or(&property);
+    UNITLESS_PROPERTIES.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_value() {
+        let px = NumericUnitConfig::default();
+        assert_eq!(numeric_value("padding", 16.0, &px), "16px");
+        assert_eq!(numeric_value("marginTop", -4.5, &px), "-4.5px");
+        assert_eq!(numeric_value("width", 0.0, &px), "0");
+        assert_eq!(numeric_value("opacity", 0.5, &px), "0.5");
+        assert_eq!(numeric_value("WebkitLineClamp", 2.0, &px), "2");
+        assert_eq!(numeric_value("--columns", 3.0, &px), "3");
+        assert_eq!(numeric_value("line-height", 1.5, &px), "1.5");
+        assert_eq!(numeric_value("-webkit-line-clamp", 2.0, &px), "2");
+        assert_eq!(numeric_value("margin-top", 4.0, &px), "4px");
+
+        let rem = NumericUnitConfig {
+            unit: "rem".to_string(),
+            base: Some(16.0),
+        };
+        assert_eq!(numeric_value("fontSize", 14.0, &rem), "0.875rem");
+        assert_eq!(numeric_value("zIndex", 10.0, &rem), "10");
+    }
+}
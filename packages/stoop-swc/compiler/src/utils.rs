@@ -1,109 +1,93 @@
 use std::collections::HashMap;
-use crate::config::StoopConfig;
-use crate::types::StyleValue;
+use crate::config::{StoopConfig, UtilityConfig};
+use crate::types::{StylePart, StyleValue};
 
-/// Common utility prop names that should be converted to CSS properties
-/// These are shortcuts like mb, mt, px, py that get converted via utility functions
-pub const UTILITY_PROPS: &[&str] = &[
+/// Built-in utilities as (name, CSS properties, scale)
+/// A `utils` entry in the config with the same name takes precedence
+const BUILTIN_UTILITIES: &[(&str, &[&str], Option<&str>)] = &[
     // Margin utilities
-    "m", "mb", "mt", "ml", "mr", "mx", "my",
+    ("m", &["margin"], Some("space")),
+    ("mb", &["marginBottom"], Some("space")),
+    ("mt", &["marginTop"], Some("space")),
+    ("ml", &["marginLeft"], Some("space")),
+    ("mr", &["marginRight"], Some("space")),
+    ("mx", &["marginLeft", "marginRight"], Some("space")),
+    ("my", &["marginTop", "marginBottom"], Some("space")),
     // Padding utilities
-    "p", "pb", "pt", "pl", "pr", "px", "py",
-    // Gap utilities
-    "gap", "rowGap", "columnGap",
-    // Size utilities (if using theme sizes)
-    "w", "h", "minW", "maxW", "minH", "maxH",
+    ("p", &["padding"], Some("space")),
+    ("pb", &["paddingBottom"], Some("space")),
+    ("pt", &["paddingTop"], Some("space")),
+    ("pl", &["paddingLeft"], Some("space")),
+    ("pr", &["paddingRight"], Some("space")),
+    ("px", &["paddingLeft", "paddingRight"], Some("space")),
+    ("py", &["paddingTop", "paddingBottom"], Some("space")),
+    // Size utilities
+    ("w", &["width"], Some("sizes")),
+    ("h", &["height"], Some("sizes")),
+    ("minW", &["minWidth"], Some("sizes")),
+    ("maxW", &["maxWidth"], Some("sizes")),
+    ("minH", &["minHeight"], Some("sizes")),
+    ("maxH", &["maxHeight"], Some("sizes")),
 ];
 
-/// Checks if a prop name is a utility prop that should be converted
-pub fn is_utility_prop(prop: &str) -> bool {
-    UTILITY_PROPS.contains(&prop)
-}
-
-/// Converts utility props to CSS properties
-/// This is a simplified version - in production, this would use actual utility functions from config
-pub fn convert_utility_to_css_property(utility: &str) -> Option<&str> {
-    match utility {
-        // Margin utilities
-        "m" => Some("margin"),
-        "mb" => Some("marginBottom"),
-        "mt" => Some("marginTop"),
-        "ml" => Some("marginLeft"),
-        "mr" => Some("marginRight"),
-        "mx" => Some("marginLeft"), // Will need special handling for both left and right
-        "my" => Some("marginTop"),  // Will need special handling for both top and bottom
-        // Padding utilities
-        "p" => Some("padding"),
-        "pb" => Some("paddingBottom"),
-        "pt" => Some("paddingTop"),
-        "pl" => Some("paddingLeft"),
-        "pr" => Some("paddingRight"),
-        "px" => Some("paddingLeft"), // Will need special handling
-        "py" => Some("paddingTop"),  // Will need special handling
-        // Gap utilities
-        "gap" => Some("gap"),
-        "rowGap" => Some("rowGap"),
-        "columnGap" => Some("columnGap"),
-        // Size utilities
-        "w" => Some("width"),
-        "h" => Some("height"),
-        "minW" => Some("minWidth"),
-        "maxW" => Some("maxWidth"),
-        "minH" => Some("minHeight"),
-        "maxH" => Some("maxHeight"),
-        _ => None,
+/// Looks up a utility by name, preferring `utils` from the config over the built-ins
+pub fn find_utility(name: &str, config: &StoopConfig) -> Option<UtilityConfig> {
+    if let Some(utility) = config.utils.get(name) {
+        return Some(utility.clone());
     }
-}
 
-/// Handles special utilities that map to multiple CSS properties
-/// Returns a map of CSS property -> value
-pub fn convert_special_utility(utility: &str, value: StyleValue) -> HashMap<String, StyleValue> {
-    let mut result = HashMap::new();
+    BUILTIN_UTILITIES
+        .iter()
+        .find(|(builtin, _, _)| *builtin == name)
+        .map(|(_, properties, scale)| UtilityConfig::Properties {
+            properties: properties.iter().map(|property| property.to_string()).collect(),
+            scale: scale.map(str::to_string),
+        })
+}
 
-    match utility {
-        "mx" => {
-            // marginLeft and marginRight
-            result.insert("marginLeft".to_string(), value.clone());
-            result.insert("marginRight".to_string(), value);
-        }
-        "my" => {
-            // marginTop and marginBottom
-            result.insert("marginTop".to_string(), value.clone());
-            result.insert("marginBottom".to_string(), value);
-        }
-        "px" => {
-            // paddingLeft and paddingRight
-            result.insert("paddingLeft".to_string(), value.clone());
-            result.insert("paddingRight".to_string(), value);
-        }
-        "py" => {
-            // paddingTop and paddingBottom
-            result.insert("paddingTop".to_string(), value.clone());
-            result.insert("paddingBottom".to_string(), value);
-        }
-        _ => {
-            // Single property utility
-            if let Some(css_prop) = convert_utility_to_css_property(utility) {
-                result.insert(css_prop.to_string(), value);
-            }
-        }
-    }
+/// Expands a property-mapping utility, applying its scale to shorthand tokens
+pub fn expand_utility(
+    properties: &[String],
+    scale: Option<&str>,
+    value: &StyleValue,
+) -> HashMap<String, StyleValue> {
+    let value = match scale {
+        Some(scale) => apply_scale(value, scale),
+        None => value.clone(),
+    };
 
-    result
+    properties
+        .iter()
+        .map(|property| (property.clone(), value.clone()))
+        .collect()
 }
 
-/// Applies utility functions to extracted utility props
-/// This would normally use utility functions from config, but for now we use simple conversion
-pub fn apply_utilities(
-    utility_props: &HashMap<String, StyleValue>,
-    _config: &StoopConfig,
-) -> HashMap<String, StyleValue> {
-    let mut css_props = HashMap::new();
+/// Pins shorthand tokens (`$md`, `-$md`) to the utility's scale (`$space.md`, `-$space.md`)
+fn apply_scale(value: &StyleValue, scale: &str) -> StyleValue {
+    let scoped = |token: &String| {
+        let (sign, name) = match token.strip_prefix('-') {
+            Some(name) => ("-", name),
+            None => ("", token.as_str()),
+        };
 
-    for (utility, value) in utility_props {
-        let converted = convert_special_utility(utility, value.clone());
-        css_props.extend(converted);
-    }
+        if name.contains('.') {
+            token.clone()
+        } else {
+            format!("{}${}.{}", sign, scale, &name[1..])
+        }
+    };
 
-    css_props
+    match value {
+        StyleValue::Token(token) => StyleValue::Token(scoped(token)),
+        StyleValue::Compound(parts) => StyleValue::Compound(
+            parts
+                .iter()
+                .map(|part| match part {
+                    StylePart::Token(token) => StylePart::Token(scoped(token)),
+                    part => part.clone(),
+                })
+                .collect(),
+        ),
+        value => value.clone(),
+    }
 }
@@ -2,41 +2,69 @@ use swc_core::ecma::{
     ast::*,
     visit::{VisitMut, VisitMutWith},
 };
+use std::collections::{BTreeMap, BTreeSet};
+
 use swc_core::common::{DUMMY_SP, SyntaxContext};
 
-use crate::config::StoopConfig;
+use crate::config::{StoopConfig, TokenOutput};
+use crate::error::{emit_error, emit_warning, StoopError};
 use crate::extractor::StyleExtractor;
 use crate::generator::CSSGenerator;
 use crate::transformer::ComponentTransformer;
+use crate::types::{CSSOutput, StyleExtraction};
 use crate::writer::StyleWriter;
 
 pub struct StoopVisitor {
     config: StoopConfig,
+    /// Path of the file being compiled, when the host provides it
+    source_file: Option<String>,
+    /// Local binding -> names it is exported under (`default` included), for the `.d.ts` sidecar
+    exported_names: BTreeMap<String, BTreeSet<String>>,
     styled_identifiers: Vec<String>,
+    css_identifiers: Vec<String>,
+    create_theme_identifiers: Vec<String>,
+    global_css_identifiers: Vec<String>,
     extractor: StyleExtractor,
     generator: CSSGenerator,
     transformer: ComponentTransformer,
     writer: StyleWriter,
     needs_react_imports: bool,
     needs_clsx_import: bool,
+    needs_responsive_import: bool,
 }
 
 impl StoopVisitor {
-    pub fn new(config: StoopConfig) -> Self {
+    pub fn new(config: StoopConfig, source_file: Option<String>) -> Self {
         Self {
+            source_file,
+            exported_names: BTreeMap::new(),
             extractor: StyleExtractor::new(&config),
             generator: CSSGenerator::new(&config),
             transformer: ComponentTransformer::new(&config),
             writer: StyleWriter::new(&config),
             styled_identifiers: vec!["styled".to_string()],
+            // css, createTheme and globalCss are common names, so they're only
+            // compiled when imported from stoop
+            css_identifiers: Vec::new(),
+            create_theme_identifiers: Vec::new(),
+            global_css_identifiers: Vec::new(),
             needs_react_imports: false,
             needs_clsx_import: false,
+            needs_responsive_import: false,
             config,
         }
     }
 
     pub fn finalize(&mut self) {
-        self.writer.write_all();
+        self.writer.write_all(self.source_file.as_deref());
+
+        if let Some(source_file) = &self.source_file {
+            self.writer.write_declarations(source_file, &self.exported_names);
+        }
+    }
+
+    fn record_export(&mut self, local: String, exported: String) {
+        self.exported_names.entry(local).or_default().insert(exported);
     }
 
     fn ensure_imports(&mut self, module: &mut swc_core::ecma::ast::Module) {
@@ -80,42 +108,48 @@ impl StoopVisitor {
             })));
         }
 
-        // Check if stoop-swc/runtime imports exist
-        let has_runtime_import = module.body.iter().any(|item| {
-            if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
-                import.src.value == "stoop-swc/runtime"
-            } else {
-                false
-            }
-        });
+        // createSelector is only referenced by generated styled components
+        let runtime_names = [
+            ("clsx", self.needs_clsx_import),
+            ("createSelector", self.needs_react_imports),
+            ("responsiveVariant", self.needs_responsive_import),
+        ];
+        let missing: Vec<&str> = runtime_names
+            .iter()
+            .filter(|(name, needed)| *needed && !Self::imports_local(module, "stoop-swc/runtime", name))
+            .map(|(name, _)| *name)
+            .collect();
 
-        if self.needs_clsx_import && !has_runtime_import {
-            // Create new stoop-swc/runtime import with clsx and createSelector
-            imports_to_add.push(ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
-                span: DUMMY_SP,
-                specifiers: vec![
-                    ImportSpecifier::Named(ImportNamedSpecifier {
-                        span: DUMMY_SP,
-                        local: Ident::new("clsx".into(), DUMMY_SP, SyntaxContext::empty()),
-                        imported: Some(ModuleExportName::Ident(Ident::new("clsx".into(), DUMMY_SP, SyntaxContext::empty()))),
-                        is_type_only: false,
-                    }),
-                    ImportSpecifier::Named(ImportNamedSpecifier {
+        if !missing.is_empty() {
+            // Extend an existing stoop-swc/runtime import so bindings are never duplicated
+            let existing = module.body.iter_mut().find_map(|item| match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+                    if import.src.value == "stoop-swc/runtime" && !import.type_only =>
+                {
+                    Some(import)
+                }
+                _ => None,
+            });
+
+            if let Some(import) = existing {
+                for name in missing {
+                    import.specifiers.push(Self::named_specifier(name));
+                }
+            } else {
+                // Create new stoop-swc/runtime import with the missing helpers
+                imports_to_add.push(ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                    span: DUMMY_SP,
+                    specifiers: missing.into_iter().map(Self::named_specifier).collect(),
+                    src: Box::new(Str {
                         span: DUMMY_SP,
-                        local: Ident::new("createSelector".into(), DUMMY_SP, SyntaxContext::empty()),
-                        imported: Some(ModuleExportName::Ident(Ident::new("createSelector".into(), DUMMY_SP, SyntaxContext::empty()))),
-                        is_type_only: false,
+                        value: "stoop-swc/runtime".into(),
+                        raw: None,
                     }),
-                ],
-                src: Box::new(Str {
-                    span: DUMMY_SP,
-                    value: "stoop-swc/runtime".into(),
-                    raw: None,
-                }),
-                type_only: false,
-                with: None,
-                phase: Default::default(),
-            })));
+                    type_only: false,
+                    with: None,
+                    phase: Default::default(),
+                })));
+            }
         }
 
         // Insert imports at the beginning
@@ -124,11 +158,49 @@ impl StoopVisitor {
         }
     }
 
+    /// Checks whether `source` is already imported with the local binding `name`
+    fn imports_local(module: &swc_core::ecma::ast::Module, source: &str, name: &str) -> bool {
+        module.body.iter().any(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) if import.src.value == source => {
+                import.specifiers.iter().any(|spec| match spec {
+                    ImportSpecifier::Named(named) => named.local.sym == name,
+                    _ => false,
+                })
+            }
+            _ => false,
+        })
+    }
+
+    fn named_specifier(name: &str) -> ImportSpecifier {
+        ImportSpecifier::Named(ImportNamedSpecifier {
+            span: DUMMY_SP,
+            local: Ident::new(name.into(), DUMMY_SP, SyntaxContext::empty()),
+            imported: Some(ModuleExportName::Ident(Ident::new(name.into(), DUMMY_SP, SyntaxContext::empty()))),
+            is_type_only: false,
+        })
+    }
+
     fn is_styled_call(&self, call: &CallExpr) -> bool {
+        Self::callee_matches(call, &self.styled_identifiers)
+    }
+
+    fn is_css_call(&self, call: &CallExpr) -> bool {
+        Self::callee_matches(call, &self.css_identifiers)
+    }
+
+    fn is_create_theme_call(&self, call: &CallExpr) -> bool {
+        Self::callee_matches(call, &self.create_theme_identifiers)
+    }
+
+    fn is_global_css_call(&self, call: &CallExpr) -> bool {
+        Self::callee_matches(call, &self.global_css_identifiers)
+    }
+
+    fn callee_matches(call: &CallExpr, identifiers: &[String]) -> bool {
         match &call.callee {
             Callee::Expr(expr) => match &**expr {
                 Expr::Ident(ident) => {
-                    self.styled_identifiers.contains(&ident.sym.to_string())
+                    identifiers.contains(&ident.sym.to_string())
                 }
                 _ => false,
             },
@@ -136,6 +208,139 @@ impl StoopVisitor {
         }
     }
 
+    /// Compiles `globalCss({...})` into the global section and returns its no-op replacement
+    fn transform_global_css_call(&mut self, call: &CallExpr) -> Expr {
+        let rules = self.extractor.extract_global(call);
+        let css = self.generator.generate_global(&rules);
+        self.writer.register_global_styles(&css);
+
+        self.transformer.create_noop_function()
+    }
+
+    /// Compiles `css(styles, variants?)` into a class name, or a variant selector function
+    fn transform_css_call(&mut self, call: &CallExpr, name: &str) -> Expr {
+        let extraction = self.extractor.extract_from_css_call(call, name);
+        let css_output = self.generator.generate(&extraction);
+        self.writer.register_styles(&css_output);
+
+        if !css_output.variant_classes.is_empty() {
+            self.needs_clsx_import = true;
+            self.mark_responsive_variants(&css_output);
+        }
+
+        self.transformer.create_class_selector(&extraction, &css_output)
+    }
+
+    /// Compiles `createTheme('name', {...})` into a scoped variable block. Calls without a
+    /// static name are left untouched.
+    fn transform_create_theme_call(&mut self, call: &CallExpr) -> Option<Expr> {
+        let Some((name, theme)) = self.extractor.extract_theme(call) else {
+            emit_error(
+                call.span,
+                &StoopError::InvalidStyle(
+                    "createTheme() needs a string literal name as its first argument, so the theme's class can be generated at build time".to_string(),
+                ),
+            );
+            return None;
+        };
+
+        if self.config.token_output == TokenOutput::Inline {
+            emit_warning(
+                call.span,
+                &StoopError::ConfigError(format!(
+                    "createTheme(\"{}\") only sets CSS variables, which component styles don't read with `tokenOutput: \"inline\"`. Add the theme to `themes` instead.",
+                    name
+                )),
+            );
+        }
+
+        let class_name = format!("{}-theme-{}", self.config.prefix, name);
+        self.writer.register_scoped_theme(&name, &class_name, &theme);
+
+        Some(self.transformer.create_theme_object(&class_name))
+    }
+
+    /// Compiles a static `css={{ ... }}` JSX prop into a class merged into `className`.
+    /// Dynamic values are reported and left untouched.
+    fn transform_css_prop(&mut self, element: &mut JSXOpeningElement) {
+        let index = match element.attrs.iter().position(|attr| match attr {
+            JSXAttrOrSpread::JSXAttr(attr) => {
+                matches!(&attr.name, JSXAttrName::Ident(name) if name.sym == "css")
+            }
+            _ => false,
+        }) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let (span, style_obj) = match &element.attrs[index] {
+            JSXAttrOrSpread::JSXAttr(attr) => {
+                let style_obj = match &attr.value {
+                    Some(JSXAttrValue::JSXExprContainer(JSXExprContainer {
+                        expr: JSXExpr::Expr(expr),
+                        ..
+                    })) => match &**expr {
+                        Expr::Object(obj) if self.extractor.is_static_style_object(obj) => Some(obj),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                (attr.span, style_obj)
+            }
+            _ => return,
+        };
+
+        let element_name = Self::jsx_element_name(&element.name);
+
+        let Some(style_obj) = style_obj else {
+            emit_warning(
+                span,
+                &StoopError::InvalidStyle(format!(
+                    "`css` prop on <{}> is not statically evaluable and was left as-is. Move dynamic styles into variants.",
+                    element_name
+                )),
+            );
+            return;
+        };
+
+        let block = self.extractor.extract_style_block(style_obj);
+        let extraction = StyleExtraction {
+            component_name: format!("{}-css", element_name),
+            element: element_name,
+            base_styles: block.styles,
+            variants: Default::default(),
+            nested_selectors: block.nested_selectors,
+            compound_variants: Vec::new(),
+            default_variants: Default::default(),
+            composed_component_class: None,
+        };
+        let css_output = self.generator.generate(&extraction);
+        self.writer.register_css_prop_styles(&css_output);
+
+        element.attrs.remove(index);
+
+        if !css_output.base_class.is_empty()
+            && self.transformer.merge_jsx_class_name(&mut element.attrs, &css_output.base_class)
+        {
+            self.needs_clsx_import = true;
+        }
+    }
+
+    fn jsx_element_name(name: &JSXElementName) -> String {
+        match name {
+            JSXElementName::Ident(ident) => ident.sym.to_string(),
+            JSXElementName::JSXMemberExpr(member) => member.prop.sym.to_string(),
+            JSXElementName::JSXNamespacedName(namespaced) => namespaced.name.sym.to_string(),
+        }
+    }
+
+    /// Generated class lists call `responsiveVariant` whenever breakpoints are configured
+    fn mark_responsive_variants(&mut self, css_output: &CSSOutput) {
+        if !css_output.variant_classes.is_empty() && !self.config.breakpoint_names().is_empty() {
+            self.needs_responsive_import = true;
+        }
+    }
+
     fn transform_styled_call(&mut self, decl: &mut VarDeclarator) {
         if let Some(init) = &decl.init {
             if let Expr::Call(call) = &**init {
@@ -148,13 +353,15 @@ impl StoopVisitor {
                     let extraction = self.extractor.extract_from_call(call, &component_name);
                     let css_output = self.generator.generate(&extraction);
                     self.writer.register_styles(&css_output);
+                    self.writer.register_component(&extraction, &css_output);
                     let new_init = self.transformer.create_component(&extraction, &css_output);
 
                     // Mark that we need React imports and clsx
                     self.needs_react_imports = true;
                     self.needs_clsx_import = true;
+                    self.mark_responsive_variants(&css_output);
 
-                    decl.init = Some(Box::new(Expr::from(new_init)));
+                    decl.init = Some(Box::new(new_init));
                 }
             }
         }
@@ -174,21 +381,24 @@ impl VisitMut for StoopVisitor {
         }
 
         for spec in &import.specifiers {
-            match spec {
-                ImportSpecifier::Named(named) => {
-                    let local_name = named.local.sym.to_string();
-
-                    let imported_name = match &named.imported {
-                        Some(ModuleExportName::Ident(id)) => id.sym.to_string(),
-                        None => local_name.clone(),
-                        _ => continue,
-                    };
+            if let ImportSpecifier::Named(named) = spec {
+                let local_name = named.local.sym.to_string();
 
-                    if imported_name == "styled" {
-                        self.styled_identifiers.push(local_name);
-                    }
+                let imported_name = match &named.imported {
+                    Some(ModuleExportName::Ident(id)) => id.sym.to_string(),
+                    None => local_name.clone(),
+                    _ => continue,
+                };
+
+                if imported_name == "styled" {
+                    self.styled_identifiers.push(local_name);
+                } else if imported_name == "css" {
+                    self.css_identifiers.push(local_name);
+                } else if imported_name == "createTheme" {
+                    self.create_theme_identifiers.push(local_name);
+                } else if imported_name == "globalCss" {
+                    self.global_css_identifiers.push(local_name);
                 }
-                _ => {}
             }
         }
 
@@ -210,19 +420,84 @@ impl VisitMut for StoopVisitor {
     }
 
     fn visit_mut_export_decl(&mut self, export: &mut ExportDecl) {
-        match &mut export.decl {
-            Decl::Var(var_decl) => {
-                for declarator in &mut var_decl.decls {
-                    self.transform_styled_call(declarator);
+        if let Decl::Var(var_decl) = &mut export.decl {
+            for declarator in &mut var_decl.decls {
+                if let Pat::Ident(ident) = &declarator.name {
+                    self.record_export(ident.id.sym.to_string(), ident.id.sym.to_string());
+                }
+                self.transform_styled_call(declarator);
+            }
+        }
+        export.visit_mut_children_with(self);
+    }
+
+    /// Records `export { Button }` and `export { Button as PrimaryButton }` so the sidecar
+    /// covers components exported after declaration
+    fn visit_mut_named_export(&mut self, export: &mut NamedExport) {
+        if export.src.is_none() {
+            for spec in &export.specifiers {
+                if let ExportSpecifier::Named(ExportNamedSpecifier {
+                    orig: ModuleExportName::Ident(orig),
+                    exported,
+                    ..
+                }) = spec
+                {
+                    let exported = match exported {
+                        Some(ModuleExportName::Ident(ident)) => ident.sym.to_string(),
+                        Some(ModuleExportName::Str(s)) => String::from_utf8_lossy(s.value.as_bytes()).into_owned(),
+                        None => orig.sym.to_string(),
+                    };
+                    self.record_export(orig.sym.to_string(), exported);
                 }
             }
-            _ => {}
         }
+
+        export.visit_mut_children_with(self);
+    }
+
+    /// Records `export default Button`
+    fn visit_mut_export_default_expr(&mut self, export: &mut ExportDefaultExpr) {
+        if let Expr::Ident(ident) = &*export.expr {
+            self.record_export(ident.sym.to_string(), "default".to_string());
+        }
+
         export.visit_mut_children_with(self);
     }
 
     fn visit_mut_var_declarator(&mut self, decl: &mut VarDeclarator) {
         self.transform_styled_call(decl);
+
+        // Name css() classes after the binding they are assigned to
+        if let (Pat::Ident(ident), Some(init)) = (&decl.name, &mut decl.init) {
+            if let Expr::Call(call) = &**init {
+                if self.is_css_call(call) {
+                    let name = ident.id.sym.to_string();
+                    **init = self.transform_css_call(call, &name);
+                }
+            }
+        }
+
         decl.visit_mut_children_with(self);
     }
+
+    fn visit_mut_jsx_opening_element(&mut self, element: &mut JSXOpeningElement) {
+        element.visit_mut_children_with(self);
+        self.transform_css_prop(element);
+    }
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        expr.visit_mut_children_with(self);
+
+        if let Expr::Call(call) = expr {
+            if self.is_global_css_call(call) {
+                *expr = self.transform_global_css_call(call);
+            } else if self.is_css_call(call) {
+                *expr = self.transform_css_call(call, "css");
+            } else if self.is_create_theme_call(call) {
+                if let Some(theme_object) = self.transform_create_theme_call(call) {
+                    *expr = theme_object;
+                }
+            }
+        }
+    }
 }
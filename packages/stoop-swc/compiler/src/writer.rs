@@ -1,21 +1,58 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
-use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use crate::config::StoopConfig;
-use crate::types::CSSOutput;
+use swc_core::common::DUMMY_SP;
+
+use crate::config::{StoopConfig, Theme, TokenOutput, VariableNaming};
+use crate::error::{emit_error, emit_warning, emit_warning_once, StoopError};
+use crate::hasher::{normalize_name, Case};
+use crate::tokens::{parse_style_string, TokenResolver, VariableNamer};
+use crate::types::{
+    CSSOutput, ComponentDeclaration, ComponentManifest, Manifest, StyleExtraction, StylePart, StyleSections,
+    StyleValue, ThemeCoverage,
+};
 
 pub struct StyleWriter {
     config: StoopConfig,
+    scoped_theme_css: String,
+    global_css: String,
     accumulated_css: String,
+    css_prop_css: String,
+    manifest: Manifest,
+    /// This file's styled components, merged into `manifest.components` under its path
+    components: BTreeMap<String, ComponentManifest>,
+    variables: VariableNamer,
+    declarations: Vec<ComponentDeclaration>,
 }
 
 impl StyleWriter {
     pub fn new(config: &StoopConfig) -> Self {
-        Self {
+        let mut writer = Self {
             config: config.clone(),
+            scoped_theme_css: String::new(),
+            global_css: String::new(),
             accumulated_css: String::new(),
+            css_prop_css: String::new(),
+            manifest: Manifest::default(),
+            components: BTreeMap::new(),
+            variables: VariableNamer::new(config),
+            declarations: Vec::new(),
+        };
+
+        writer.record_tokens(&config.theme);
+
+        if let Some(themes) = &config.themes {
+            for (theme_name, theme) in themes {
+                writer
+                    .manifest
+                    .themes
+                    .insert(theme_name.clone(), Self::theme_coverage(&config.theme, theme));
+                writer.record_tokens(theme);
+            }
         }
+
+        writer
     }
 
     pub fn register_styles(&mut self, output: &CSSOutput) {
@@ -23,11 +60,261 @@ impl StyleWriter {
         self.accumulated_css.push('\n');
     }
 
-    pub fn write_all(&self) {
-        if self.accumulated_css.is_empty() {
+    /// Records a styled component's classes and default variants in the manifest
+    pub fn register_component(&mut self, extraction: &StyleExtraction, output: &CSSOutput) {
+        let variants = output
+            .variant_classes
+            .iter()
+            .map(|(name, classes)| {
+                let classes = classes.iter().map(|(value, class)| (value.clone(), class.clone())).collect();
+                (name.clone(), classes)
+            })
+            .collect();
+
+        self.components.insert(
+            extraction.component_name.clone(),
+            ComponentManifest {
+                base_class: output.base_class.clone(),
+                variants,
+                default_variants: extraction.default_variants.clone().into_iter().collect(),
+            },
+        );
+
+        let variants = extraction
+            .variants
+            .iter()
+            .map(|(name, values)| {
+                let mut values: Vec<String> = values.keys().cloned().collect();
+                values.sort();
+                (name.clone(), values)
+            })
+            .collect();
+
+        self.declarations.push(ComponentDeclaration {
+            name: extraction.component_name.clone(),
+            element: extraction.element.clone(),
+            variants,
+            default_variants: extraction.default_variants.clone().into_iter().collect(),
+        });
+    }
+
+    /// Writes a `.d.ts` for `source_file` under `<output.dir>/types` when `declarations`
+    /// is enabled, mirroring its path (`src/Button.tsx` -> `types/src/Button.d.ts`) and
+    /// typing the variant props of each exported styled component. `exported` maps local
+    /// names to the names they're exported under.
+    pub fn write_declarations(&self, source_file: &str, exported: &BTreeMap<String, BTreeSet<String>>) {
+        if !self.config.declarations {
+            return;
+        }
+
+        let components: Vec<&ComponentDeclaration> = self
+            .declarations
+            .iter()
+            .filter(|component| exported.contains_key(&component.name))
+            .collect();
+
+        if components.is_empty() {
             return;
         }
 
+        let source = Path::new(source_file);
+        let responsive = !self.config.breakpoint_names().is_empty();
+
+        // `styled(Base, ...)` is typed after `Base` when it is declared in the same file
+        let element_types: Vec<String> = components
+            .iter()
+            .map(|component| {
+                if component.element.starts_with(|ch: char| ch.is_ascii_lowercase()) {
+                    Self::quote(&component.element)
+                } else if components.iter().any(|other| other.name == component.element) {
+                    format!("typeof {}", component.element)
+                } else {
+                    "ElementType".to_string()
+                }
+            })
+            .collect();
+
+        let mut dts = format!(
+            "// Generated by stoop-swc from {}. Do not edit.\n\n",
+            source.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+        );
+
+        if element_types.iter().any(|element| element == "ElementType") {
+            dts.push_str("import type { ElementType } from \"react\";\n");
+        }
+
+        dts.push_str(if responsive {
+            "import type { ResponsiveVariant, StyledComponent } from \"stoop-swc/runtime\";\n"
+        } else {
+            "import type { StyledComponent } from \"stoop-swc/runtime\";\n"
+        });
+
+        for (component, element) in components.iter().zip(&element_types) {
+            dts.push_str(&format!("\nexport interface {}Variants {{\n", component.name));
+
+            for (variant, values) in &component.variants {
+                if let Some(default) = component.default_variants.get(variant) {
+                    dts.push_str(&format!("  /** @default {} */\n", Self::variant_literal(default)));
+                }
+
+                // `true`/`false` keys are matched against boolean props
+                let mut types: Vec<String> = Vec::new();
+                for value in values {
+                    let value_type = match value.as_str() {
+                        "true" | "false" => "boolean".to_string(),
+                        _ => Self::quote(value),
+                    };
+                    if !types.contains(&value_type) {
+                        types.push(value_type);
+                    }
+                }
+
+                let union = types.join(" | ");
+                let value_type = if responsive {
+                    format!("ResponsiveVariant<{}>", union)
+                } else {
+                    union
+                };
+
+                dts.push_str(&format!("  {}?: {};\n", Self::property_key(variant), value_type));
+            }
+
+            dts.push_str("}\n\n");
+            dts.push_str(&format!(
+                "declare const {}: StyledComponent<{}, {}Variants>;\n",
+                component.name, element, component.name
+            ));
+        }
+
+        let specifiers: Vec<String> = components
+            .iter()
+            .flat_map(|component| {
+                exported[&component.name].iter().map(|name| {
+                    if *name == component.name {
+                        name.clone()
+                    } else if Self::property_key(name) == *name {
+                        format!("{} as {}", component.name, name)
+                    } else {
+                        format!("{} as {}", component.name, Self::quote(name))
+                    }
+                })
+            })
+            .collect();
+        dts.push_str(&format!("\nexport {{ {} }};\n", specifiers.join(", ")));
+
+        let path = self.declaration_path(source_file);
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Warning: Failed to create directory {:?}: {}", parent, e);
+                return;
+            }
+        }
+        if let Err(e) = fs::write(&path, dts) {
+            eprintln!("Warning: Failed to write {:?}: {}", path, e);
+        }
+    }
+
+    /// Where the declarations for `source_file` go. Files outside the working directory
+    /// are placed at the top of `types` by file name.
+    fn declaration_path(&self, source_file: &str) -> PathBuf {
+        let relative = PathBuf::from(Self::source_key(Some(source_file)));
+        let relative = if relative.is_absolute()
+            || relative.components().any(|part| matches!(part, std::path::Component::ParentDir))
+        {
+            PathBuf::from(relative.file_name().unwrap_or_default())
+        } else {
+            relative
+        };
+
+        PathBuf::from(&self.config.output.dir)
+            .join("types")
+            .join(relative.with_extension("d.ts"))
+    }
+
+    /// A variant value as it appears in props: `true`, or `"md"`
+    fn variant_literal(value: &str) -> String {
+        match value {
+            "true" | "false" => value.to_string(),
+            _ => Self::quote(value),
+        }
+    }
+
+    /// An interface property name, quoted unless it is a plain identifier
+    fn property_key(name: &str) -> String {
+        let is_identifier = name.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_' || ch == '$')
+            && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$');
+
+        if is_identifier {
+            name.to_string()
+        } else {
+            Self::quote(name)
+        }
+    }
+
+    /// Registers styles compiled from JSX `css` props. They are written after all
+    /// component rules so they override the component's own styles.
+    pub fn register_css_prop_styles(&mut self, output: &CSSOutput) {
+        self.css_prop_css.push_str(&output.css);
+        self.css_prop_css.push('\n');
+    }
+
+    /// Registers a `createTheme()` theme as a variable block scoped to its class and the
+    /// configured theme selectors, so subtrees can opt into it
+    pub fn register_scoped_theme(&mut self, name: &str, class_name: &str, theme: &Theme) {
+        let merged = self.config.theme.merged_with(theme);
+        let resolver = TokenResolver::new(&merged, &self.variables);
+        Self::report_reference_cycle(&resolver, &format!("theme `{}`", name));
+        self.report_unknown_references(&resolver, theme, &format!("theme `{}`", name));
+
+        let coverage = Self::theme_coverage(&self.config.theme, theme);
+        Self::report_added_tokens(name, &coverage);
+        self.manifest.themes.insert(name.to_string(), coverage);
+        self.record_tokens(theme);
+
+        let mut selectors = vec![format!(".{}", class_name)];
+        selectors.extend(self.config.theme_selectors(name));
+
+        self.scoped_theme_css.push_str(&format!("\n{} {{\n", selectors.join(", ")));
+        Self::write_theme_variables(
+            &mut self.scoped_theme_css,
+            if self.config.theme_selector.nested { &merged } else { theme },
+            &resolver,
+        );
+        self.scoped_theme_css.push_str("}\n");
+    }
+
+    /// Registers `globalCss()` rules, which are written ahead of all component rules
+    pub fn register_global_styles(&mut self, css: &str) {
+        self.global_css.push_str(css);
+    }
+
+    /// Writes the stylesheet, manifest and token modules. `source_file` keys this file's
+    /// components in the manifest.
+    pub fn write_all(&self, source_file: Option<&str>) {
+        // A file without styles still has to clear what an earlier compile of it wrote,
+        // e.g. after its last `styled()` call is removed
+        if self.accumulated_css.is_empty()
+            && self.global_css.is_empty()
+            && self.scoped_theme_css.is_empty()
+            && self.css_prop_css.is_empty()
+        {
+            let source_key = Self::source_key(source_file);
+            if !self.read_style_sections().contains_key(&source_key)
+                && !self.read_manifest().components.contains_key(&source_key)
+            {
+                return;
+            }
+        }
+
+        self.write_stylesheet(source_file);
+
+        let manifest = self.write_manifest(source_file);
+        self.write_token_modules(&manifest.tokens);
+    }
+
+    /// Replaces this file's sections in `styles.json` and rebuilds the stylesheet from
+    /// every file's sections, so rules from files compiled earlier are kept
+    fn write_stylesheet(&self, source_file: Option<&str>) {
         let output_path = self.get_output_path();
 
         // Create parent directory if it doesn't exist
@@ -38,24 +325,190 @@ impl StyleWriter {
             }
         }
 
-        // Try to create or append to the file
-        match fs::File::create(&output_path) {
-            Ok(mut file) => {
-                let theme_css = self.generate_theme_css();
-                if let Err(e) = file.write_all(theme_css.as_bytes()) {
-                    eprintln!("Warning: Failed to write theme CSS: {}", e);
-                }
-                if let Err(e) = file.write_all(b"\n\n") {
-                    eprintln!("Warning: Failed to write separator: {}", e);
+        let sections_path = PathBuf::from(&self.config.output.dir).join("styles.json");
+        let mut sections = self.read_style_sections();
+
+        let source_key = Self::source_key(source_file);
+        let own = StyleSections {
+            scoped_themes: self.scoped_theme_css.clone(),
+            global: self.global_css.clone(),
+            components: self.accumulated_css.clone(),
+            css_props: self.css_prop_css.clone(),
+        };
+        if own.is_empty() {
+            sections.remove(&source_key);
+        } else {
+            sections.insert(source_key, own);
+        }
+
+        match serde_json::to_string_pretty(&sections) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&sections_path, json) {
+                    eprintln!("Warning: Failed to write {:?}: {}", sections_path, e);
                 }
-                if let Err(e) = file.write_all(self.accumulated_css.as_bytes()) {
-                    eprintln!("Warning: Failed to write CSS: {}", e);
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to serialize style sections: {}", e);
+            }
+        }
+
+        // Globals come first so component rules override them
+        let mut css = self.generate_theme_css();
+        for section in sections.values() {
+            css.push_str(&section.scoped_themes);
+        }
+        css.push_str("\n\n");
+        for section in sections.values() {
+            css.push_str(&section.global);
+        }
+        for section in sections.values() {
+            css.push_str(&section.components);
+        }
+        for section in sections.values() {
+            css.push_str(&section.css_props);
+        }
+
+        if let Err(e) = fs::write(&output_path, css) {
+            eprintln!("Warning: Failed to write CSS file {:?}: {}", output_path, e);
+        }
+    }
+
+    /// Writes `tokens.js`, mapping every token to its CSS variable for use outside
+    /// stylesheets (charts, canvas), and `tokens.d.ts` with its types and per-scale
+    /// token unions, so a misspelled `'$primray'` fails type-checking
+    /// `tokens` is the merged manifest's, so `createTheme()` tokens from other files are kept.
+    fn write_token_modules(&self, tokens: &BTreeMap<String, BTreeSet<String>>) {
+        let dir = PathBuf::from(&self.config.output.dir);
+        let header = "// Generated by stoop-swc from the theme. Do not edit.\n\n";
+
+        let mut js = format!("{}export const tokens = {{\n", header);
+        let mut dts = format!("{}export interface Tokens {{\n", header);
+        let mut unions = String::new();
+        let mut references = Vec::new();
+
+        for (js_scale, keys) in tokens {
+            if keys.is_empty() {
+                continue;
+            }
+
+            let scale = normalize_name(js_scale, Case::Kebab);
+            let type_name = format!("{}{}Token", js_scale[..1].to_uppercase(), &js_scale[1..]);
+
+            js.push_str(&format!("  {}: {{\n", js_scale));
+            dts.push_str(&format!("  {}: {{\n", js_scale));
+
+            for key in keys {
+                let quoted = Self::quote(key);
+                let variable = format!("var({})", self.variables.token(&scale, key));
+                js.push_str(&format!("    {}: {},\n", quoted, Self::quote(&variable)));
+                dts.push_str(&format!("    {}: string;\n", quoted));
+            }
+
+            js.push_str("  },\n");
+            dts.push_str("  };\n");
+
+            let names: Vec<String> = keys.iter().map(|key| Self::quote(key)).collect();
+            unions.push_str(&format!("export type {} = {};\n", type_name, names.join(" | ")));
+
+            references.push(format!("`$${{{}}}`", type_name));
+            references.push(format!("`${}.${{{}}}`", js_scale, type_name));
+            if matches!(scale.as_str(), "space" | "sizes") {
+                references.push(format!("`-$${{{}}}`", type_name));
+                references.push(format!("`-${}.${{{}}}`", js_scale, type_name));
+            }
+        }
+
+        js.push_str("};\n");
+        dts.push_str("}\n\n");
+        dts.push_str(&unions);
+
+        if references.is_empty() {
+            dts.push_str("\nexport type Token = never;\n");
+        } else {
+            dts.push_str(&format!("\nexport type Token =\n  | {};\n", references.join("\n  | ")));
+        }
+
+        dts.push_str("\nexport declare const tokens: Tokens;\n");
+
+        // Narrows the runtime's style value types, so `styled()` and `css()` objects
+        // reject unknown tokens too
+        dts.push_str("\ndeclare module \"stoop-swc\" {\n  interface Register {\n    token: Token;\n  }\n}\n");
+
+        for (filename, contents) in [("tokens.js", js), ("tokens.d.ts", dts)] {
+            let path = dir.join(filename);
+            if let Err(e) = fs::write(&path, contents) {
+                eprintln!("Warning: Failed to write {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// A JS string literal for `value`
+    fn quote(value: &str) -> String {
+        serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+    }
+
+    /// Merges this file's entries into `manifest.json`. Other files' components are kept,
+    /// while this file's entry is replaced so removed components drop out. Returns the
+    /// merged manifest.
+    fn write_manifest(&self, source_file: Option<&str>) -> Manifest {
+        let manifest_path = PathBuf::from(&self.config.output.dir).join("manifest.json");
+        let mut manifest = self.read_manifest();
+
+        let source_key = Self::source_key(source_file);
+        if self.components.is_empty() {
+            manifest.components.remove(&source_key);
+        } else {
+            manifest.components.insert(source_key, self.components.clone());
+        }
+        manifest.themes.extend(self.manifest.themes.clone());
+        manifest.variables.extend(self.manifest.variables.clone());
+        for (scale, keys) in &self.manifest.tokens {
+            manifest.tokens.entry(scale.clone()).or_default().extend(keys.iter().cloned());
+        }
+
+        match serde_json::to_string_pretty(&manifest) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&manifest_path, json) {
+                    eprintln!("Warning: Failed to write manifest {:?}: {}", manifest_path, e);
                 }
             }
             Err(e) => {
-                eprintln!("Warning: Failed to create CSS file {:?}: {}", output_path, e);
+                eprintln!("Warning: Failed to serialize manifest: {}", e);
             }
         }
+
+        manifest
+    }
+
+    /// Every file's sections from `styles.json`, empty when it doesn't exist yet
+    fn read_style_sections(&self) -> BTreeMap<String, StyleSections> {
+        fs::read_to_string(PathBuf::from(&self.config.output.dir).join("styles.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// The existing `manifest.json`, empty when it doesn't exist yet
+    fn read_manifest(&self) -> Manifest {
+        fs::read_to_string(PathBuf::from(&self.config.output.dir).join("manifest.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// The source file's path relative to the working directory, with `/` separators
+    fn source_key(source_file: Option<&str>) -> String {
+        let Some(source_file) = source_file else {
+            return "<unknown>".to_string();
+        };
+
+        let path = Path::new(source_file);
+        let relative = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+            .unwrap_or_else(|| path.to_path_buf());
+
+        relative.to_string_lossy().replace('\\', "/")
     }
 
     fn get_output_path(&self) -> PathBuf {
@@ -66,91 +519,689 @@ impl StyleWriter {
     }
 
     fn generate_theme_css(&self) -> String {
-        let mut css = String::from(":root {\n");
+        let strategy = &self.config.theme_selector;
 
-        // Generate CSS variables for all theme scales
-        if let Some(colors) = &self.config.theme.colors {
-            for (key, value) in colors {
-                css.push_str(&format!("  --colors-{}: {};\n", key, value));
-            }
+        // A named base theme also gets its own selectors, so it can be re-applied
+        // inside another theme's subtree
+        let mut root_selectors = vec![":root".to_string()];
+        if let Some(base_name) = &strategy.base_name {
+            root_selectors.extend(self.config.theme_selectors(base_name));
         }
 
-        if let Some(space) = &self.config.theme.space {
-            for (key, value) in space {
-                css.push_str(&format!("  --space-{}: {};\n", key, value));
-            }
+        let resolver = TokenResolver::new(&self.config.theme, &self.variables);
+        Self::report_reference_cycle(&resolver, "base theme");
+        self.report_unknown_references(&resolver, &self.config.theme, "base theme");
+
+        let mut css = String::new();
+
+        if self.config.property_registration.enabled {
+            css.push_str(&self.generate_property_registrations());
         }
 
-        if let Some(font_sizes) = &self.config.theme.font_sizes {
-            for (key, value) in font_sizes {
-                css.push_str(&format!("  --font-sizes-{}: {};\n", key, value));
+        css.push_str(&format!("{} {{\n", root_selectors.join(", ")));
+
+        // Generate CSS variables for all theme scales
+        Self::write_theme_variables(&mut css, &self.config.theme, &resolver);
+
+        css.push_str("}\n");
+
+        if let Some(themes) = &self.config.themes {
+            let mut theme_names: Vec<&String> = themes.keys().collect();
+            theme_names.sort();
+
+            for theme_name in theme_names {
+                let theme = &themes[theme_name];
+
+                // References resolve against the base theme with this theme's overrides applied
+                let merged = self.config.theme.merged_with(theme);
+                let resolver = TokenResolver::new(&merged, &self.variables);
+                Self::report_reference_cycle(&resolver, &format!("theme `{}`", theme_name));
+                self.report_unknown_references(&resolver, theme, &format!("theme `{}`", theme_name));
+
+                if let Some(coverage) = self.manifest.themes.get(theme_name) {
+                    Self::report_added_tokens(theme_name, coverage);
+                }
+
+                let mut variables = String::new();
+                Self::write_theme_variables(
+                    &mut variables,
+                    if strategy.nested { &merged } else { theme },
+                    &resolver,
+                );
+
+                let selectors = self.config.theme_selectors(theme_name);
+                if !selectors.is_empty() {
+                    css.push_str(&format!("\n{} {{\n{}}}\n", selectors.join(", "), variables));
+                }
+
+                if let Some(media_query) = self.config.theme_media_query(theme_name) {
+                    css.push_str(&format!(
+                        "\n{} {{\n  {} {{\n",
+                        media_query,
+                        self.config.unthemed_root_selector(),
+                    ));
+                    for line in variables.lines() {
+                        css.push_str(&format!("  {}\n", line));
+                    }
+                    css.push_str("  }\n}\n");
+                }
             }
         }
 
-        if let Some(font_weights) = &self.config.theme.font_weights {
-            for (key, value) in font_weights {
-                css.push_str(&format!("  --font-weights-{}: {};\n", key, value));
+        css
+    }
+
+    /// Generates `@property` rules for the base theme's tokens. References to other
+    /// tokens are registered with the value they resolve to, since `initial-value` can't
+    /// use `var()`. Tokens without a known syntax, or whose value can't be an
+    /// `initial-value` (relative units) or doesn't match the syntax, are left unregistered.
+    fn generate_property_registrations(&self) -> String {
+        let overrides: HashMap<String, &String> = self
+            .config
+            .property_registration
+            .syntax
+            .iter()
+            .map(|(scale, syntax)| (normalize_name(scale, Case::Kebab), syntax))
+            .collect();
+
+        let resolver = TokenResolver::with_output(&self.config.theme, &self.variables, TokenOutput::Inline);
+        let mut css = String::new();
+
+        for (scale, tokens) in self.config.theme.scales() {
+            let syntax = match overrides.get(scale) {
+                Some(syntax) => syntax.as_str(),
+                None => match Self::inferred_syntax(scale) {
+                    Some(syntax) => syntax,
+                    None => continue,
+                },
+            };
+
+            let mut keys: Vec<&String> = tokens.keys().collect();
+            keys.sort();
+
+            for key in keys {
+                let value = resolver.resolve_theme_value(&tokens[key], scale);
+
+                if !Self::is_computationally_independent(&value) {
+                    continue;
+                }
+
+                if !Self::matches_syntax(&value, syntax) {
+                    emit_warning_once(
+                        DUMMY_SP,
+                        &StoopError::InvalidStyle(format!(
+                            "`{}.{}` is not registered with @property: `{}` does not match syntax \"{}\"",
+                            normalize_name(scale, Case::Camel), key, value, syntax,
+                        )),
+                    );
+                    continue;
+                }
+
+                css.push_str(&format!(
+                    "@property {} {{\n  syntax: \"{}\";\n  inherits: true;\n  initial-value: {};\n}}\n\n",
+                    resolver.variable_name(scale, key), syntax, value
+                ));
             }
         }
 
-        if let Some(line_heights) = &self.config.theme.line_heights {
-            for (key, value) in line_heights {
-                css.push_str(&format!("  --line-heights-{}: {};\n", key, value));
-            }
+        css
+    }
+
+    /// `@property` syntax for a scale's tokens, when one fits every value in the scale
+    fn inferred_syntax(scale: &str) -> Option<&'static str> {
+        match scale {
+            "colors" => Some("<color>"),
+            "space" | "sizes" | "font-sizes" | "radii" => Some("<length-percentage>"),
+            "letter-spacings" => Some("<length>"),
+            "font-weights" => Some("<number>"),
+            "line-heights" => Some("<number> | <length-percentage>"),
+            "z-indices" => Some("<integer>"),
+            _ => None,
         }
+    }
+
+    /// Whether a value fits one of a syntax's `|` alternatives. Only single-value data
+    /// types are checked; other components (`*`, multipliers, keywords) are trusted.
+    fn matches_syntax(value: &str, syntax: &str) -> bool {
+        const LENGTH_UNITS: &[&str] = &[
+            "px", "cm", "mm", "q", "in", "pt", "pc", "em", "rem", "ex", "rex", "ch", "rch", "ic", "ric",
+            "cap", "rcap", "lh", "rlh", "vw", "vh", "vi", "vb", "vmin", "vmax", "svw", "svh", "lvw",
+            "lvh", "dvw", "dvh", "cqw", "cqh", "cqi", "cqb", "cqmin", "cqmax",
+        ];
+        const NOT_COLORS: &[&str] = &[
+            "auto", "none", "inherit", "initial", "unset", "revert", "normal",
+        ];
+        const COLOR_FUNCTIONS: &[&str] = &[
+            "rgb(", "rgba(", "hsl(", "hsla(", "hwb(", "lab(", "lch(", "oklab(", "oklch(", "color(",
+            "color-mix(", "light-dark(",
+        ];
+        const MATH_FUNCTIONS: &[&str] = &["calc(", "min(", "max(", "clamp("];
+
+        let value = value.trim().to_lowercase();
+        let number_end = value
+            .find(|ch: char| !(ch.is_ascii_digit() || matches!(ch, '.' | '-' | '+')))
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(number_end);
+        let number = number.parse::<f64>().ok();
+        let is_math = MATH_FUNCTIONS.iter().any(|function| value.starts_with(function));
 
-        if let Some(letter_spacings) = &self.config.theme.letter_spacings {
-            for (key, value) in letter_spacings {
-                css.push_str(&format!("  --letter-spacings-{}: {};\n", key, value));
+        let is_length = is_math || (number.is_some() && (LENGTH_UNITS.contains(&unit) || value == "0"));
+        let is_percentage = is_math || (number.is_some() && unit == "%");
+
+        syntax.split('|').map(str::trim).any(|component| match component {
+            "<length>" => is_length,
+            "<percentage>" => is_percentage,
+            "<length-percentage>" => is_length || is_percentage,
+            "<number>" => is_math || (number.is_some() && unit.is_empty()),
+            "<integer>" => is_math || value.parse::<i64>().is_ok(),
+            "<color>" => {
+                (value.starts_with('#')
+                    && matches!(value.len(), 4 | 5 | 7 | 9)
+                    && value[1..].chars().all(|ch| ch.is_ascii_hexdigit()))
+                    || COLOR_FUNCTIONS.iter().any(|function| value.starts_with(function))
+                    || (value.chars().all(|ch| ch.is_ascii_alphabetic()) && !NOT_COLORS.contains(&value.as_str()))
             }
+            _ => true,
+        })
+    }
+
+    /// `initial-value` must not depend on other properties: no `var()`, `currentcolor`,
+    /// or font/viewport-relative units
+    fn is_computationally_independent(value: &str) -> bool {
+        const RELATIVE_UNITS: &[&str] = &[
+            "em", "rem", "ex", "rex", "ch", "rch", "ic", "ric", "cap", "rcap", "lh", "rlh",
+            "vw", "vh", "vi", "vb", "vmin", "vmax", "svw", "svh", "lvw", "lvh", "dvw", "dvh",
+            "cqw", "cqh", "cqi", "cqb", "cqmin", "cqmax",
+        ];
+
+        let value = value.to_lowercase();
+
+        if value.contains("var(") || value.contains("currentcolor") || value.contains("env(") {
+            return false;
         }
 
-        if let Some(radii) = &self.config.theme.radii {
-            for (key, value) in radii {
-                css.push_str(&format!("  --radii-{}: {};\n", key, value));
+        // Units are the letters that directly follow a number, e.g. `1.5rem`, `100dvh`
+        !value
+            .split(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '.'))
+            .filter(|word| word.starts_with(|ch: char| ch.is_ascii_digit() || ch == '.'))
+            .map(|word| word.trim_start_matches(|ch: char| ch.is_ascii_digit() || ch == '.'))
+            .any(|unit| RELATIVE_UNITS.contains(&unit))
+    }
+
+    /// Writes a theme's variables, resolving token references in their values
+    fn write_theme_variables(css: &mut String, theme: &Theme, resolver: &TokenResolver) {
+        for (scale, tokens) in theme.scales() {
+            for (key, value) in tokens {
+                css.push_str(&format!(
+                    "  {}: {};\n",
+                    resolver.variable_name(scale, key),
+                    resolver.resolve_theme_value(value, scale)
+                ));
             }
         }
+    }
+
+    /// Records a theme's token names for `tokens.d.ts`/`tokens.js`. With hashed variable
+    /// names, each token's variable also goes in the manifest, so tooling can map
+    /// `colors.primary` back to it.
+    ///
+    /// Hashed names of configured tokens are lengthened on collision by `VariableNamer`;
+    /// tokens only a `createTheme()` call defines can still collide, which is reported.
+    fn record_tokens(&mut self, theme: &Theme) {
+        let hashed = self.config.variable_names == VariableNaming::Hashed;
+
+        for (scale, tokens) in theme.scales() {
+            let js_scale = normalize_name(scale, Case::Camel);
+            let mut keys: Vec<&String> = tokens.keys().collect();
+            keys.sort();
+
+            for key in keys {
+                self.manifest.tokens.entry(js_scale.clone()).or_default().insert(key.clone());
+
+                if !hashed {
+                    continue;
+                }
 
-        if let Some(shadows) = &self.config.theme.shadows {
-            for (key, value) in shadows {
-                css.push_str(&format!("  --shadows-{}: {};\n", key, value));
+                let token = format!("{}.{}", js_scale, key);
+                let variable = self.variables.token(scale, key);
+
+                if let Some((other, _)) = self
+                    .manifest
+                    .variables
+                    .iter()
+                    .find(|(other, other_variable)| **other_variable == variable && **other != token)
+                {
+                    emit_error(
+                        DUMMY_SP,
+                        &StoopError::ConfigError(format!(
+                            "tokens `{}` and `{}` hash to the same variable `{}`. Add `{}` to the base theme or rename it.",
+                            other, token, variable, token,
+                        )),
+                    );
+                }
+
+                self.manifest.variables.insert(token, variable);
             }
         }
+    }
 
-        if let Some(transitions) = &self.config.theme.transitions {
-            for (key, value) in transitions {
-                css.push_str(&format!("  --transitions-{}: {};\n", key, value));
-            }
+    /// Compares an alternate theme's tokens against the base theme
+    fn theme_coverage(base: &Theme, theme: &Theme) -> ThemeCoverage {
+        let base_tokens = base.token_names();
+        let theme_tokens = theme.token_names();
+
+        ThemeCoverage {
+            added: theme_tokens
+                .iter()
+                .filter(|name| !base_tokens.contains(name))
+                .cloned()
+                .collect(),
+            not_overridden: base_tokens
+                .iter()
+                .filter(|name| !theme_tokens.contains(name))
+                .cloned()
+                .collect(),
         }
+    }
 
-        css.push_str("}\n");
+    /// Warns about tokens only an alternate theme defines: outside that theme they have
+    /// no value, so styles using them fall back to nothing
+    fn report_added_tokens(theme_name: &str, coverage: &ThemeCoverage) {
+        if !coverage.added.is_empty() {
+            emit_warning_once(
+                DUMMY_SP,
+                &StoopError::ThemeMismatch(format!(
+                    "theme `{}` defines tokens missing from the base theme: {}",
+                    theme_name,
+                    coverage.added.join(", "),
+                )),
+            );
+        }
+    }
 
-        if let Some(themes) = &self.config.themes {
-            for (theme_name, theme) in themes {
-                css.push_str(&format!("\n[data-theme=\"{}\"] {{\n", theme_name));
+    /// Reports `$token` references in theme values that match no token, which would
+    /// otherwise be written as a variable that is never set
+    fn report_unknown_references(&self, resolver: &TokenResolver, theme: &Theme, theme_label: &str) {
+        for (scale, tokens) in theme.scales() {
+            let mut keys: Vec<&String> = tokens.keys().collect();
+            keys.sort();
 
-                // Generate overrides for all theme scales in alternate themes
-                if let Some(colors) = &theme.colors {
-                    for (key, value) in colors {
-                        css.push_str(&format!("  --colors-{}: {};\n", key, value));
-                    }
-                }
-                if let Some(space) = &theme.space {
-                    for (key, value) in space {
-                        css.push_str(&format!("  --space-{}: {};\n", key, value));
+            for key in keys {
+                let references = match parse_style_string(tokens[key].clone()) {
+                    StyleValue::Static(_) => Vec::new(),
+                    StyleValue::Token(token) => vec![token],
+                    StyleValue::Compound(parts) => parts
+                        .into_iter()
+                        .filter_map(|part| match part {
+                            StylePart::Token(token) => Some(token),
+                            StylePart::Static(_) => None,
+                        })
+                        .collect(),
+                };
+
+                for reference in references {
+                    let Err(suggestion) = resolver.check_token(&reference) else {
+                        continue;
+                    };
+
+                    let mut message = format!(
+                        "`{}` in `{}.{}` of the {} is not defined in the theme",
+                        reference, normalize_name(scale, Case::Camel), key, theme_label,
+                    );
+                    if let Some(suggestion) = suggestion {
+                        message.push_str(&format!(". Did you mean `{}`?", suggestion));
                     }
-                }
-                if let Some(font_sizes) = &theme.font_sizes {
-                    for (key, value) in font_sizes {
-                        css.push_str(&format!("  --font-sizes-{}: {};\n", key, value));
+
+                    let error = StoopError::UnknownToken(message);
+                    if self.config.strict {
+                        emit_error(DUMMY_SP, &error);
+                    } else {
+                        emit_warning(DUMMY_SP, &error);
                     }
                 }
-                // Add other scales as needed...
-
-                css.push_str("}\n");
             }
         }
+    }
 
-        css
+    /// Reports theme tokens that reference each other in a loop, which CSS would
+    /// otherwise silently treat as invalid
+    fn report_reference_cycle(resolver: &TokenResolver, theme_label: &str) {
+        if let Some(cycle) = resolver.find_reference_cycle() {
+            emit_error(
+                DUMMY_SP,
+                &StoopError::ConfigError(format!(
+                    "token references in the {} form a cycle: {}",
+                    theme_label,
+                    cycle.join(" -> "),
+                )),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::StyleBlock;
+
+    /// An unstyled component whose rules are `css`
+    fn test_component(name: &str, css: &str) -> (StyleExtraction, CSSOutput) {
+        let extraction = StyleExtraction {
+            component_name: name.to_string(),
+            element: "div".to_string(),
+            base_styles: HashMap::new(),
+            variants: HashMap::new(),
+            nested_selectors: HashMap::new(),
+            compound_variants: Vec::new(),
+            default_variants: HashMap::new(),
+            composed_component_class: None,
+        };
+        let output = CSSOutput {
+            base_class: format!("stoop-{}-base", name),
+            selector_class: String::new(),
+            variant_classes: HashMap::new(),
+            compound_variant_classes: Vec::new(),
+            css: css.to_string(),
+        };
+        (extraction, output)
+    }
+
+    fn register_test_component(writer: &mut StyleWriter, name: &str, css: &str) {
+        let (extraction, output) = test_component(name, css);
+        writer.register_styles(&output);
+        writer.register_component(&extraction, &output);
+    }
+
+    #[test]
+    fn test_write_stylesheet_merges_files() {
+        let dir = std::env::temp_dir().join(format!("stoop-stylesheet-{}", std::process::id()));
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+
+        let mut global = StyleWriter::new(&config);
+        global.register_global_styles("body {\n  margin: 0;\n}\n");
+        global.write_all(Some("src/global.ts"));
+
+        let mut card = StyleWriter::new(&config);
+        register_test_component(&mut card, "Card", ".stoop-Card-base {\n  padding: 0;\n}");
+        card.write_all(Some("src/Card.tsx"));
+
+        // Recompiling a file replaces its own rules only
+        let mut card = StyleWriter::new(&config);
+        register_test_component(&mut card, "Card", ".stoop-Card-base {\n  padding: 4px;\n}");
+        card.write_all(Some("src/Card.tsx"));
+
+        let css = fs::read_to_string(dir.join("styles.css")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(css.contains("body {\n  margin: 0;\n}"), "{}", css);
+        assert!(css.contains("padding: 4px;"), "{}", css);
+        assert!(!css.contains("padding: 0;"), "{}", css);
+        assert!(css.find("body {").unwrap() < css.find(".stoop-Card-base").unwrap(), "{}", css);
+    }
+
+    #[test]
+    fn test_write_all_clears_removed_components() {
+        let dir = std::env::temp_dir().join(format!("stoop-removed-{}", std::process::id()));
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+
+        let mut button = StyleWriter::new(&config);
+        register_test_component(&mut button, "Button", ".stoop-Button-base {\n  padding: 0;\n}");
+        button.write_all(Some("src/Button.tsx"));
+
+        let mut card = StyleWriter::new(&config);
+        register_test_component(&mut card, "Card", ".stoop-Card-base {\n  padding: 0;\n}");
+        card.write_all(Some("src/Card.tsx"));
+
+        // The file's last styled() call was removed
+        StyleWriter::new(&config).write_all(Some("src/Button.tsx"));
+
+        let css = fs::read_to_string(dir.join("styles.css")).unwrap();
+        let manifest: Manifest =
+            serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!css.contains(".stoop-Button-base"), "{}", css);
+        assert!(css.contains(".stoop-Card-base"), "{}", css);
+        assert!(!manifest.components.contains_key("src/Button.tsx"));
+        assert!(manifest.components.contains_key("src/Card.tsx"));
+    }
+
+    #[test]
+    fn test_write_stylesheet_keeps_css_props() {
+        let dir = std::env::temp_dir().join(format!("stoop-css-props-{}", std::process::id()));
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+
+        let mut page = StyleWriter::new(&config);
+        let (_, output) = test_component("Page", ".stoop-css-1a2b3c {\n  margin: 0;\n}");
+        page.register_css_prop_styles(&output);
+        page.write_all(Some("src/page.tsx"));
+
+        let mut card = StyleWriter::new(&config);
+        register_test_component(&mut card, "Card", ".stoop-Card-base {\n  padding: 0;\n}");
+        card.write_all(Some("src/Card.tsx"));
+
+        let css = fs::read_to_string(dir.join("styles.css")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(css.contains(".stoop-css-1a2b3c"), "{}", css);
+        assert!(css.find(".stoop-Card-base").unwrap() < css.find(".stoop-css-1a2b3c").unwrap(), "{}", css);
+    }
+
+    #[test]
+    fn test_write_stylesheet_keeps_scoped_themes() {
+        let dir = std::env::temp_dir().join(format!("stoop-scoped-themes-{}", std::process::id()));
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+        config.theme.colors = Some(HashMap::from([("primary".to_string(), "#0070f3".to_string())]));
+
+        let mut themed = StyleWriter::new(&config);
+        let brand = Theme {
+            colors: Some(HashMap::from([("primary".to_string(), "#ff0000".to_string())])),
+            ..Theme::default()
+        };
+        themed.register_scoped_theme("brand", "stoop-theme-brand", &brand);
+        themed.write_all(Some("src/theme.ts"));
+
+        let mut card = StyleWriter::new(&config);
+        register_test_component(&mut card, "Card", ".stoop-Card-base {\n  padding: 0;\n}");
+        card.write_all(Some("src/Card.tsx"));
+
+        let css = fs::read_to_string(dir.join("styles.css")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(css.contains(".stoop-theme-brand"), "{}", css);
+        assert!(css.contains("--stoop-colors-primary: #ff0000;"), "{}", css);
+        assert!(css.find(":root").unwrap() < css.find(".stoop-theme-brand").unwrap(), "{}", css);
+    }
+
+    #[test]
+    fn test_write_manifest_merges_files() {
+        let dir = std::env::temp_dir().join(format!("stoop-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+
+        let compile = |component: &str| {
+            let mut writer = StyleWriter::new(&config);
+            let extraction = StyleExtraction {
+                component_name: component.to_string(),
+                element: "div".to_string(),
+                base_styles: HashMap::new(),
+                variants: HashMap::new(),
+                nested_selectors: HashMap::new(),
+                compound_variants: Vec::new(),
+                default_variants: HashMap::new(),
+                composed_component_class: None,
+            };
+            let output = CSSOutput {
+                base_class: format!("stoop-{}-base", component),
+                selector_class: String::new(),
+                variant_classes: HashMap::new(),
+                compound_variant_classes: Vec::new(),
+                css: String::new(),
+            };
+            writer.register_component(&extraction, &output);
+            writer
+        };
+
+        compile("Button").write_manifest(Some("src/Button.tsx"));
+        compile("Card").write_manifest(Some("src/Card.tsx"));
+        compile("IconButton").write_manifest(Some("src/Button.tsx"));
+
+        let manifest: Manifest =
+            serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(manifest.components.len(), 2);
+        assert!(manifest.components["src/Card.tsx"].contains_key("Card"));
+        let button_file: Vec<&String> = manifest.components["src/Button.tsx"].keys().collect();
+        assert_eq!(button_file, ["IconButton"]);
+    }
+
+    #[test]
+    fn test_write_token_modules_merges_files() {
+        let dir = std::env::temp_dir().join(format!("stoop-tokens-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+        config.theme.colors = Some(HashMap::from([("primary".to_string(), "#0070f3".to_string())]));
+
+        let compile = |file: &str, scoped: Option<Theme>| {
+            let mut writer = StyleWriter::new(&config);
+            if let Some(theme) = scoped {
+                writer.register_scoped_theme("brand", "stoop-theme-brand", &theme);
+            }
+            let manifest = writer.write_manifest(Some(file));
+            writer.write_token_modules(&manifest.tokens);
+        };
+
+        let brand = Theme {
+            colors: Some(HashMap::from([("brand".to_string(), "#ff0000".to_string())])),
+            ..Theme::default()
+        };
+        compile("src/Brand.tsx", Some(brand));
+        compile("src/Card.tsx", None);
+
+        let js = fs::read_to_string(dir.join("tokens.js")).unwrap();
+        let dts = fs::read_to_string(dir.join("tokens.d.ts")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(js.contains("\"brand\": \"var(--stoop-colors-brand)\""), "{}", js);
+        assert!(js.contains("\"primary\""), "{}", js);
+        assert!(dts.contains("export type ColorsToken = \"brand\" | \"primary\";"), "{}", dts);
+        assert!(dts.contains("interface Register {\n    token: Token;\n  }"), "{}", dts);
+    }
+
+    #[test]
+    fn test_property_registrations_check_values() {
+        let mut config = StoopConfig::default();
+        config.property_registration.enabled = true;
+        config.theme.colors = Some(HashMap::from([
+            ("primary".to_string(), "#0070f3".to_string()),
+            ("accent".to_string(), "$primary".to_string()),
+        ]));
+        config.theme.sizes = Some(HashMap::from([
+            ("auto".to_string(), "auto".to_string()),
+            ("md".to_string(), "16px".to_string()),
+            ("full".to_string(), "100%".to_string()),
+        ]));
+
+        let css = StyleWriter::new(&config).generate_property_registrations();
+
+        assert!(
+            css.contains("@property --stoop-colors-accent {\n  syntax: \"<color>\";\n  inherits: true;\n  initial-value: #0070f3;"),
+            "{}",
+            css
+        );
+        assert!(css.contains("@property --stoop-sizes-md"), "{}", css);
+        assert!(css.contains("@property --stoop-sizes-full"), "{}", css);
+        assert!(!css.contains("--stoop-sizes-auto"), "{}", css);
+    }
+
+    #[test]
+    fn test_matches_syntax() {
+        assert!(StyleWriter::matches_syntax("1.5rem", "<length-percentage>"));
+        assert!(StyleWriter::matches_syntax("0", "<length>"));
+        assert!(StyleWriter::matches_syntax("calc(4px * -1)", "<length-percentage>"));
+        assert!(!StyleWriter::matches_syntax("auto", "<length-percentage>"));
+        assert!(StyleWriter::matches_syntax("1.5", "<number> | <length-percentage>"));
+        assert!(!StyleWriter::matches_syntax("bold", "<number>"));
+        assert!(!StyleWriter::matches_syntax("1.5", "<integer>"));
+        assert!(StyleWriter::matches_syntax("rebeccapurple", "<color>"));
+        assert!(StyleWriter::matches_syntax("rgb(0 0 0 / 50%)", "<color>"));
+        assert!(!StyleWriter::matches_syntax("#12", "<color>"));
+        assert!(!StyleWriter::matches_syntax("inherit", "<color>"));
+        assert!(StyleWriter::matches_syntax("ease-in", "*"));
+    }
+
+    #[test]
+    fn test_theme_coverage_uses_js_scale_names() {
+        let base = Theme {
+            font_sizes: Some(HashMap::from([("sm".to_string(), "14px".to_string())])),
+            ..Theme::default()
+        };
+        let theme = Theme {
+            font_sizes: Some(HashMap::from([("lg".to_string(), "18px".to_string())])),
+            ..Theme::default()
+        };
+
+        let coverage = StyleWriter::theme_coverage(&base, &theme);
+
+        assert_eq!(coverage.added, vec!["fontSizes.lg"]);
+        assert_eq!(coverage.not_overridden, vec!["fontSizes.sm"]);
+    }
+
+    #[test]
+    fn test_write_declarations_follows_exports() {
+        let dir = std::env::temp_dir().join(format!("stoop-declarations-{}", std::process::id()));
+
+        let mut config = StoopConfig::default();
+        config.output.dir = dir.to_string_lossy().into_owned();
+        config.declarations = true;
+
+        let mut writer = StyleWriter::new(&config);
+        let extraction = StyleExtraction {
+            component_name: "Button".to_string(),
+            element: "button".to_string(),
+            base_styles: HashMap::new(),
+            variants: HashMap::from([(
+                "size".to_string(),
+                HashMap::from([("sm".to_string(), StyleBlock::default()), ("md".to_string(), StyleBlock::default())]),
+            )]),
+            nested_selectors: HashMap::new(),
+            compound_variants: Vec::new(),
+            default_variants: HashMap::from([("size".to_string(), "md".to_string())]),
+            composed_component_class: None,
+        };
+        let output = CSSOutput {
+            base_class: "stoop-Button-base".to_string(),
+            selector_class: String::new(),
+            variant_classes: HashMap::new(),
+            compound_variant_classes: Vec::new(),
+            css: String::new(),
+        };
+        writer.register_component(&extraction, &output);
+
+        let exported = BTreeMap::from([(
+            "Button".to_string(),
+            BTreeSet::from(["PrimaryButton".to_string(), "default".to_string()]),
+        )]);
+        writer.write_declarations("src/Button.tsx", &exported);
+
+        let dts = fs::read_to_string(dir.join("types/src/Button.d.ts")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(dts.contains("  /** @default \"md\" */\n  size?: \"md\" | \"sm\";"), "{}", dts);
+        assert!(dts.contains("declare const Button: StyledComponent<\"button\", ButtonVariants>;"), "{}", dts);
+        assert!(dts.contains("export { Button as PrimaryButton, Button as default };"), "{}", dts);
     }
 }
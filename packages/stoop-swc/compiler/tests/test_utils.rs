@@ -1,5 +1,5 @@
 use swc_core::ecma::{
-    parser::{parse_file_as_module, EsSyntax, Syntax, TsSyntax},
+    parser::{parse_file_as_module, Syntax, TsSyntax},
     visit::VisitMutWith,
     codegen::{text_writer::JsWriter, Emitter, Config},
 };
@@ -31,7 +31,7 @@ pub fn transform(code: &str) -> String {
         ).expect("Failed to parse");
 
         let config = StoopConfig::default();
-        let mut visitor = StoopVisitor::new(config);
+        let mut visitor = StoopVisitor::new(config, None);
 
         module.visit_mut_with(&mut visitor);
         visitor.finalize();
@@ -0,0 +1,3 @@
+export function createTheme(): any {
+  throw new Error("[stoop-swc] createTheme() must be compiled by the SWC plugin at build time.");
+}
@@ -0,0 +1,7 @@
+import type { CheckedStyles } from "./types";
+
+export function css<const Styles extends Record<string, any>>(
+  styles: Styles & CheckedStyles<Styles>,
+): any {
+  throw new Error("[stoop-swc] css() must be compiled by the SWC plugin at build time.");
+}
@@ -0,0 +1,3 @@
+export function globalCss(): any {
+  throw new Error("[stoop-swc] globalCss() must be compiled by the SWC plugin at build time.");
+}
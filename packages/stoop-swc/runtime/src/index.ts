@@ -1,7 +1,19 @@
 export { styled } from "./styled";
+export { css } from "./css";
 export { keyframes } from "./keyframes";
+export { globalCss } from "./global-css";
+export { createTheme } from "./create-theme";
 export { ThemeProvider, useTheme } from "./provider";
 export type { ThemeProviderProps, ThemeContextValue } from "./provider";
 export { clsx } from "./classnames";
 export { createSelector } from "./selector";
-export type { StyledComponent, VariantProps, StyledComponentRef } from "./types";
+export { responsiveVariant } from "./responsive";
+export type {
+  StyledComponent,
+  VariantProps,
+  VariantPropsOf,
+  StyledComponentRef,
+  ResponsiveVariant,
+  Register,
+  Token,
+} from "./types";
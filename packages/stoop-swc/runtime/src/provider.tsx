@@ -9,16 +9,26 @@ export interface ThemeContextValue {
 
 const ThemeContext = createContext<ThemeContextValue | null>(null);
 
+/** Theme name that leaves `<html>` unthemed, so `themeSelector.media` follows the OS setting */
+const SYSTEM_THEME = "system";
+
 export interface ThemeProviderProps {
   children: ReactNode;
+  /** Theme used until one is chosen; `"system"` follows `prefers-color-scheme` */
   defaultTheme?: string;
   storageKey?: string;
+  /** Attribute set on `<html>`; match `themeSelector.attribute`. Pass `false` to skip it */
+  attribute?: string | false;
+  /** Class set on `<html>`, with `{name}` replaced by the theme; match `themeSelector.class` */
+  classTemplate?: string;
 }
 
 export function ThemeProvider({
   children,
   defaultTheme = "light",
   storageKey = "stoop-theme",
+  attribute = "data-theme",
+  classTemplate,
 }: ThemeProviderProps) {
   const [theme, setThemeState] = useState(() => {
     if (typeof window === "undefined") {
@@ -43,8 +53,33 @@ export function ThemeProvider({
   };
 
   useEffect(() => {
-    document.documentElement.setAttribute("data-theme", theme);
-  }, [theme]);
+    const root = document.documentElement;
+
+    // No attribute or class, so the `prefers-color-scheme` fallback applies
+    if (theme === SYSTEM_THEME) {
+      if (attribute) {
+        root.removeAttribute(attribute);
+      }
+
+      return;
+    }
+
+    if (attribute) {
+      root.setAttribute(attribute, theme);
+    }
+
+    if (!classTemplate) {
+      return;
+    }
+
+    const className = classTemplate.replace("{name}", theme);
+
+    root.classList.add(className);
+
+    return () => {
+      root.classList.remove(className);
+    };
+  }, [theme, attribute, classTemplate]);
 
   return <ThemeContext.Provider value={{ setTheme, theme }}>{children}</ThemeContext.Provider>;
 }
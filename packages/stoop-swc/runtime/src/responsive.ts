@@ -0,0 +1,24 @@
+/**
+ * Maps a responsive variant value such as `{ "@initial": "sm", "@bp2": "lg" }`
+ * to the breakpoint classes generated by the SWC plugin.
+ */
+
+export function responsiveVariant(value: unknown, classes: Record<string, string>): string {
+  if (typeof value !== "object" || value === null) {
+    return "";
+  }
+
+  const result: string[] = [];
+
+  for (const [breakpoint, variantValue] of Object.entries(value)) {
+    const className = classes[String(variantValue)];
+
+    if (!className) {
+      continue;
+    }
+
+    result.push(breakpoint === "@initial" ? className : `${className}-${breakpoint.slice(1)}`);
+  }
+
+  return result.join(" ");
+}
@@ -1,10 +1,15 @@
 import type { JSX } from "react";
+import type { CheckedStyles, StyledComponent, VariantPropsOf } from "./types";
 
 export function styled<
   E extends keyof JSX.IntrinsicElements,
-  BaseStyles extends Record<string, any> = {},
-  Variants extends Record<string, Record<string, any>> = {},
->(element: E, baseStyles?: BaseStyles, variants?: Variants): any {
+  const BaseStyles extends Record<string, any> = {},
+  const Variants extends Record<string, Record<string, any>> = {},
+>(
+  element: E,
+  baseStyles?: BaseStyles & CheckedStyles<BaseStyles>,
+  variants?: Variants & CheckedStyles<Variants>,
+): StyledComponent<E, VariantPropsOf<Variants>> {
   throw new Error(
     "[stoop-swc] styled() must be compiled by the SWC plugin.\n\n" +
       "Setup: https://stoop.dev/setup\n\n" +
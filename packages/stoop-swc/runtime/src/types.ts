@@ -10,6 +10,30 @@ export type CSS = CSSProperties & {
   [key: string]: string | number | CSS | undefined;
 };
 
+/**
+ * Augmented by the generated `tokens.d.ts` with `token: Token`, the union of every
+ * theme token. Until it is included in the project, any token is accepted.
+ */
+export interface Register {}
+
+/** A theme token reference such as `"$primary"`, `"$space.md"` or `"-$space.md"` */
+export type Token = Register extends { token: infer T extends string } ? T : string;
+
+/**
+ * A style object as written, with every value that is a lone token reference
+ * narrowed to `Token`, so `color: "$primray"` fails type-checking. Values mixing
+ * tokens with other text (`"1px solid $primary"`) are left to the compiler.
+ */
+export type CheckedStyles<S> = {
+  [K in keyof S]: S[K] extends `${string} ${string}`
+    ? S[K]
+    : S[K] extends `$${string}` | `-$${string}`
+      ? Token
+      : S[K] extends Record<string, any>
+        ? CheckedStyles<S[K]>
+        : S[K];
+};
+
 export interface ThemeContextValue {
   theme: string;
   setTheme: (theme: string) => void;
@@ -23,17 +47,40 @@ export type VariantProps<T> = T extends (...args: any) => any
     : {}
   : {};
 
+/**
+ * A variant prop value, or an object picking a value per breakpoint:
+ * `{ "@initial": "sm", "@bp2": "lg" }`.
+ */
+export type ResponsiveVariant<T> = T | { [breakpoint: `@${string}`]: T };
+
+/** `true`/`false` variant keys are matched against boolean props */
+type VariantValue<K> = K extends "true" | "false" ? boolean : K;
+
+/**
+ * The variant props of a `styled()` variants object, typed from its keys.
+ * `compoundVariants` and `defaultVariants` configure the component and aren't props.
+ */
+export type VariantPropsOf<V> = {
+  [K in Exclude<keyof V, "compoundVariants" | "defaultVariants">]?: ResponsiveVariant<
+    VariantValue<keyof V[K]>
+  >;
+};
+
 export interface StyledComponentRef {
   readonly __isStoopStyled: true;
   readonly __stoopClassName: string;
   toString(): string;
 }
 
+/**
+ * A compiled styled component. `V` holds its variant props, inferred by `styled()`
+ * and written to the declarations under `.stoop/types` when `declarations` is enabled.
+ */
 export type StyledComponent<
   E extends ElementType,
-  V extends Record<string, any>,
+  V extends Record<string, any> = {},
 > = ForwardRefExoticComponent<
-  ComponentPropsWithRef<E> & VariantProps<typeof import("./styled").styled>
+  Omit<ComponentPropsWithRef<E>, keyof V> & V & { as?: ElementType }
 > & {
   selector: StyledComponentRef;
 };
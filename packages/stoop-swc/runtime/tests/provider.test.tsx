@@ -39,6 +39,30 @@ describe("ThemeProvider", () => {
     expect(document.documentElement.getAttribute("data-theme")).toBe("dark");
   });
 
+  it("leaves the root unthemed for the system theme", () => {
+    document.documentElement.setAttribute("data-theme", "dark");
+
+    render(
+      <ThemeProvider classTemplate="theme-{name}" defaultTheme="system">
+        <div>Test</div>
+      </ThemeProvider>,
+    );
+
+    expect(document.documentElement.hasAttribute("data-theme")).toBe(false);
+    expect(document.documentElement.classList.contains("theme-system")).toBe(false);
+  });
+
+  it("sets the theme class from classTemplate", () => {
+    render(
+      <ThemeProvider attribute={false} classTemplate="theme-{name}" defaultTheme="dark">
+        <div>Test</div>
+      </ThemeProvider>,
+    );
+
+    expect(document.documentElement.classList.contains("theme-dark")).toBe(true);
+    expect(document.documentElement.hasAttribute("data-theme")).toBe(false);
+  });
+
   it("persists theme to localStorage", () => {
     const TestComponent = () => {
       const { setTheme } = useTheme();
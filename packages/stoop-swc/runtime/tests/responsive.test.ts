@@ -0,0 +1,21 @@
+import { describe, it, expect } from "vitest";
+import { responsiveVariant } from "../src/responsive";
+
+describe("responsiveVariant", () => {
+  const classes = { lg: "btn-size-lg", sm: "btn-size-sm" };
+
+  it("maps breakpoints to suffixed classes", () => {
+    expect(responsiveVariant({ "@initial": "sm", "@bp2": "lg" }, classes)).toBe(
+      "btn-size-sm btn-size-lg-bp2",
+    );
+  });
+
+  it("ignores plain values", () => {
+    expect(responsiveVariant("sm", classes)).toBe("");
+    expect(responsiveVariant(undefined, classes)).toBe("");
+  });
+
+  it("skips unknown variant values", () => {
+    expect(responsiveVariant({ "@bp1": "xl" }, classes)).toBe("");
+  });
+});